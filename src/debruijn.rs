@@ -0,0 +1,122 @@
+use crate::eval::fresh;
+use crate::parser::{Expr, Param};
+use std::collections::HashSet;
+
+/// A nameless representation of terms, where bound variables refer to their binder by position.
+///
+/// Alpha-equivalent expressions convert to equal terms.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Term {
+    /// A bound variable, counting the binders between it and its abstraction, starting at zero.
+    Var(usize),
+    /// A variable not bound inside the term, such as a global.
+    Free(String),
+    Abs(Box<Term>),
+    App(Box<Term>, Box<Term>),
+}
+
+impl Term {
+    /// Collects the names of all free variables in the term.
+    pub fn free_names(&self) -> HashSet<String> {
+        fn collect(term: &Term, names: &mut HashSet<String>) {
+            match term {
+                Term::Var(_) => {}
+                Term::Free(name) => {
+                    names.insert(name.clone());
+                }
+                Term::Abs(body) => collect(body, names),
+                Term::App(callee, argument) => {
+                    collect(callee, names);
+                    collect(argument, names);
+                }
+            }
+        }
+
+        let mut names = HashSet::new();
+        collect(self, &mut names);
+        names
+    }
+}
+
+/// Checks whether two expressions are equal up to renaming of bound variables, so `λab.a` and
+/// `λxy.x` are, but `λa.b` and `λa.c` aren't.
+pub fn alpha_eq(a: &Expr, b: &Expr) -> bool {
+    Term::from(a) == Term::from(b)
+}
+
+/// Converts an expression, desugaring `λab.x` into `λa.λb.x`. Error nodes become a free name that
+/// prints like them.
+impl From<&Expr> for Term {
+    fn from(expr: &Expr) -> Self {
+        fn convert(expr: &Expr, scope: &mut Vec<String>) -> Term {
+            match expr {
+                Expr::Name { name, .. } => match scope.iter().rev().position(|bound| bound == name)
+                {
+                    Some(index) => Term::Var(index),
+                    None => Term::Free(name.clone()),
+                },
+                Expr::Error { .. } => Term::Free(crate::parser::ERROR.to_string()),
+                Expr::Application {
+                    callee, argument, ..
+                } => Term::App(
+                    Box::new(convert(callee, scope)),
+                    Box::new(convert(argument, scope)),
+                ),
+                Expr::Abstraction { params, body, .. } => {
+                    scope.extend(params.iter().map(|param| param.name.clone()));
+                    let mut term = convert(body, scope);
+                    for _ in params {
+                        scope.pop();
+                        term = Term::Abs(Box::new(term));
+                    }
+                    term
+                }
+            }
+        }
+
+        convert(expr, &mut Vec::new())
+    }
+}
+
+/// Converts a term back to a named expression, picking fresh names for its binders and merging
+/// nested abstractions into `λab.x`.
+impl From<&Term> for Expr {
+    fn from(term: &Term) -> Self {
+        fn convert(term: &Term, scope: &mut Vec<String>, used: &mut HashSet<String>) -> Expr {
+            match term {
+                Term::Var(index) => {
+                    let name = scope
+                        .iter()
+                        .rev()
+                        .nth(*index)
+                        .expect("de Bruijn index without a binder");
+                    Expr::name(name.clone())
+                }
+                Term::Free(name) => Expr::name(name.clone()),
+                Term::App(callee, argument) => {
+                    Expr::application(convert(callee, scope, used), convert(argument, scope, used))
+                }
+                Term::Abs(body) => {
+                    let param = fresh(used);
+                    scope.push(param.clone());
+                    used.insert(param.clone());
+                    let body = convert(body, scope, used);
+                    used.remove(&param);
+                    scope.pop();
+
+                    match body {
+                        Expr::Abstraction {
+                            mut params, body, ..
+                        } => {
+                            params.insert(0, Param::new(param));
+                            Expr::abstraction(params, *body)
+                        }
+                        body => Expr::abstraction(vec![Param::new(param)], body),
+                    }
+                }
+            }
+        }
+
+        convert(term, &mut Vec::new(), &mut term.free_names())
+    }
+}
@@ -0,0 +1,171 @@
+use crate::debruijn::Term;
+use crate::parser::Expr;
+
+/// Decodes a Church numeral `λfx.f (f … x)` into the number of applications of `f`.
+pub fn church_numeral(expr: &Expr) -> Option<usize> {
+    numeral(&Term::from(expr))
+}
+
+/// Decodes the Church booleans `λab.a` and `λab.b`.
+pub fn church_bool(expr: &Expr) -> Option<bool> {
+    boolean(&Term::from(expr))
+}
+
+/// Decodes a Church pair `λf.f a b` into its two components.
+pub fn church_pair(expr: &Expr) -> Option<(Expr, Expr)> {
+    pair(&Term::from(expr)).map(|(first, second)| (Expr::from(&first), Expr::from(&second)))
+}
+
+/// Decodes a Church list `λcn.c x (c y … n)`, the right fold over its elements.
+pub fn church_list(expr: &Expr) -> Option<Vec<Expr>> {
+    church(&Term::from(expr)).map(|elements| elements.iter().map(Expr::from).collect())
+}
+
+/// Decodes a Scott list, built from `λnc.n` for the empty list and `λnc.c head tail`.
+pub fn scott_list(expr: &Expr) -> Option<Vec<Expr>> {
+    scott(&Term::from(expr)).map(|elements| elements.iter().map(Expr::from).collect())
+}
+
+/// Describes what `expr` encodes, like `3`, `TRUE` or `(1, [FALSE])`, if it is one of the
+/// encodings above. Terms with several readings, like `λab.b`, list all of them: `0 = FALSE`.
+///
+/// Empty lists coincide with booleans and zero, so they are only shown inside other values.
+pub fn describe(expr: &Expr) -> Option<String> {
+    let readings = readings(&Term::from(expr), false);
+    (!readings.is_empty()).then(|| readings.join(" = "))
+}
+
+fn readings(term: &Term, nested: bool) -> Vec<String> {
+    let mut readings = Vec::new();
+
+    if let Some(number) = numeral(term) {
+        readings.push(number.to_string());
+    }
+    if let Some(boolean) = boolean(term) {
+        readings.push(if boolean { "TRUE" } else { "FALSE" }.to_string());
+    }
+    if let Some((first, second)) = pair(term) {
+        readings.push(format!(
+            "({}, {})",
+            nested_reading(&first),
+            nested_reading(&second)
+        ));
+    }
+    for list in [church(term), scott(term)].into_iter().flatten() {
+        if nested || !list.is_empty() {
+            let elements = list.iter().map(nested_reading).collect::<Vec<_>>();
+            readings.push(format!("[{}]", elements.join(", ")));
+        }
+    }
+
+    readings
+}
+
+/// The preferred reading of a component of a pair or list, falling back to the term itself.
+fn nested_reading(term: &Term) -> String {
+    readings(term, true)
+        .into_iter()
+        .next()
+        .unwrap_or_else(|| Expr::from(term).to_string())
+}
+
+fn numeral(term: &Term) -> Option<usize> {
+    let mut body = binders(term, 2)?;
+    let mut count = 0;
+    loop {
+        match body {
+            Term::Var(0) => return Some(count),
+            Term::App(callee, argument) if **callee == Term::Var(1) => {
+                count += 1;
+                body = argument;
+            }
+            _ => return None,
+        }
+    }
+}
+
+fn boolean(term: &Term) -> Option<bool> {
+    match binders(term, 2)? {
+        Term::Var(1) => Some(true),
+        Term::Var(0) => Some(false),
+        _ => None,
+    }
+}
+
+fn pair(term: &Term) -> Option<(Term, Term)> {
+    match binders(term, 1)? {
+        Term::App(callee, second) => match &**callee {
+            Term::App(selector, first) if **selector == Term::Var(0) => {
+                Some((unbind(first, 1)?, unbind(second, 1)?))
+            }
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn church(term: &Term) -> Option<Vec<Term>> {
+    let mut body = binders(term, 2)?;
+    let mut elements = Vec::new();
+    loop {
+        match body {
+            Term::Var(0) => return Some(elements),
+            Term::App(callee, rest) => match &**callee {
+                Term::App(cons, head) if **cons == Term::Var(1) => {
+                    elements.push(unbind(head, 2)?);
+                    body = rest;
+                }
+                _ => return None,
+            },
+            _ => return None,
+        }
+    }
+}
+
+fn scott(term: &Term) -> Option<Vec<Term>> {
+    let mut elements = Vec::new();
+    let mut list = term.clone();
+    loop {
+        match binders(&list, 2)? {
+            Term::Var(1) => return Some(elements),
+            Term::App(callee, tail) => match &**callee {
+                Term::App(cons, head) if **cons == Term::Var(0) => {
+                    elements.push(unbind(head, 2)?);
+                    list = unbind(tail, 2)?;
+                }
+                _ => return None,
+            },
+            _ => return None,
+        }
+    }
+}
+
+/// Returns the body below exactly `count` leading abstractions of `term`.
+fn binders(mut term: &Term, count: usize) -> Option<&Term> {
+    for _ in 0..count {
+        match term {
+            Term::Abs(body) => term = body,
+            _ => return None,
+        }
+    }
+    Some(term)
+}
+
+/// Moves `term` out from under `count` binders, which fails if it refers to any of them.
+fn unbind(term: &Term, count: usize) -> Option<Term> {
+    fn shift(term: &Term, count: usize, depth: usize) -> Option<Term> {
+        Some(match term {
+            Term::Var(index) if *index < depth => Term::Var(*index),
+            Term::Var(index) if *index < depth + count => return None,
+            Term::Var(index) => Term::Var(index - count),
+            Term::Free(name) => Term::Free(name.clone()),
+            Term::Abs(body) => Term::Abs(Box::new(shift(body, count, depth + 1)?)),
+            Term::App(callee, argument) => Term::App(
+                Box::new(shift(callee, count, depth)?),
+                Box::new(shift(argument, count, depth)?),
+            ),
+        })
+    }
+
+    shift(term, count, 0)
+}
@@ -0,0 +1,375 @@
+use crate::diagnostic::{Diagnostic, DiagnosticKind, Severity, Span};
+use crate::eval::{free_vars, fresh, substitute, Strategy};
+use crate::parser::{Expr, Param, Statement};
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+/// Which definitions of a program refer to which.
+#[derive(Debug, Clone)]
+pub struct Graph {
+    /// The name of every definition and the span of the name, in the order of the program.
+    definitions: Vec<(String, Option<Span>)>,
+    /// The indices of the definitions each definition refers to.
+    edges: Vec<BTreeSet<usize>>,
+    /// Whether each definition contains an abstraction. A cycle of definitions without one only
+    /// ever expands to itself.
+    abstractions: Vec<bool>,
+}
+
+impl Graph {
+    /// Builds the graph of the definitions in `statements`. A name that is defined several times
+    /// refers to its last definition.
+    pub fn new(statements: &[Statement]) -> Self {
+        let (definitions, bodies): (Vec<_>, Vec<_>) = statements
+            .iter()
+            .filter_map(|statement| match statement {
+                Statement::Definition { name, expr, span } => {
+                    Some(((name.clone(), span.clone()), expr))
+                }
+                Statement::Expr(_) => None,
+            })
+            .unzip();
+
+        let index = definitions
+            .iter()
+            .enumerate()
+            .map(|(i, (name, _))| (name.as_str(), i))
+            .collect::<HashMap<_, _>>();
+        let edges = bodies
+            .iter()
+            .map(|body| {
+                free_vars(body)
+                    .iter()
+                    .filter_map(|name| index.get(name.as_str()).copied())
+                    .collect()
+            })
+            .collect();
+        let abstractions = bodies
+            .iter()
+            .map(|body| contains_abstraction(body))
+            .collect();
+
+        Self {
+            definitions,
+            edges,
+            abstractions,
+        }
+    }
+
+    /// The names of the definitions that the last definition of `name` refers to directly,
+    /// ordered by where they are defined.
+    pub fn dependencies(&self, name: &str) -> Vec<&str> {
+        match self
+            .definitions
+            .iter()
+            .rposition(|(defined, _)| defined == name)
+        {
+            Some(i) => self.edges[i].iter().map(|&j| self.name(j)).collect(),
+            None => Vec::new(),
+        }
+    }
+
+    /// The groups of definitions that refer to themselves, directly or through the others in
+    /// the group. Both the groups and the names in them are in the order of the program.
+    pub fn cycles(&self) -> Vec<Vec<&str>> {
+        self.cycle_indices()
+            .into_iter()
+            .map(|cycle| cycle.into_iter().map(|i| self.name(i)).collect())
+            .collect()
+    }
+
+    fn name(&self, i: usize) -> &str {
+        &self.definitions[i].0
+    }
+
+    /// Whether any of the definitions in `cycle` contains an abstraction.
+    fn has_abstraction(&self, cycle: &[usize]) -> bool {
+        cycle.iter().any(|&i| self.abstractions[i])
+    }
+
+    /// The strongly connected components with a cycle in them, found with Tarjan's algorithm.
+    fn cycle_indices(&self) -> Vec<Vec<usize>> {
+        struct Tarjan<'g> {
+            edges: &'g [BTreeSet<usize>],
+            index: Vec<Option<usize>>,
+            low: Vec<usize>,
+            stack: Vec<usize>,
+            on_stack: Vec<bool>,
+            visited: usize,
+            components: Vec<Vec<usize>>,
+        }
+
+        impl Tarjan<'_> {
+            fn visit(&mut self, v: usize) {
+                self.index[v] = Some(self.visited);
+                self.low[v] = self.visited;
+                self.visited += 1;
+                self.stack.push(v);
+                self.on_stack[v] = true;
+
+                for &w in self.edges[v].iter() {
+                    match self.index[w] {
+                        None => {
+                            self.visit(w);
+                            self.low[v] = self.low[v].min(self.low[w]);
+                        }
+                        Some(index) if self.on_stack[w] => self.low[v] = self.low[v].min(index),
+                        Some(_) => {}
+                    }
+                }
+
+                if Some(self.low[v]) == self.index[v] {
+                    let start = self
+                        .stack
+                        .iter()
+                        .rposition(|&w| w == v)
+                        .expect("v is on the stack");
+                    let mut component = self.stack.split_off(start);
+                    for &w in &component {
+                        self.on_stack[w] = false;
+                    }
+                    component.sort_unstable();
+                    self.components.push(component);
+                }
+            }
+        }
+
+        let len = self.definitions.len();
+        let mut tarjan = Tarjan {
+            edges: &self.edges,
+            index: vec![None; len],
+            low: vec![0; len],
+            stack: Vec::new(),
+            on_stack: vec![false; len],
+            visited: 0,
+            components: Vec::new(),
+        };
+        for v in 0..len {
+            if tarjan.index[v].is_none() {
+                tarjan.visit(v);
+            }
+        }
+
+        let mut cycles = tarjan
+            .components
+            .into_iter()
+            .filter(|component| {
+                component.len() > 1 || self.edges[component[0]].contains(&component[0])
+            })
+            .collect::<Vec<_>>();
+        cycles.sort_unstable();
+        cycles
+    }
+}
+
+/// Reports every group of recursive definitions in `statements`, labelling each definition with
+/// the ones in the group it refers to.
+///
+/// Recursive definitions work under lazy strategies, which only expand a name once it is needed,
+/// so they are warnings. Strict strategies expand them forever, call-by-value only until they are
+/// rewritten with [`Combinator::Z`]. A group without any abstraction,
+/// like `F := F`, never gets anywhere under any strategy and is an error.
+pub fn check(statements: &[Statement]) -> Vec<Diagnostic> {
+    let graph = Graph::new(statements);
+    let mut diagnostics = Vec::new();
+
+    for cycle in graph.cycle_indices() {
+        let labels = cycle
+            .iter()
+            .filter_map(|&i| {
+                let span = graph.definitions[i].1.clone()?;
+                let referred = graph.edges[i]
+                    .iter()
+                    .filter(|j| cycle.contains(j))
+                    .map(|&j| match j == i {
+                        true => "itself".to_string(),
+                        false => format!("`{}`", graph.name(j)),
+                    })
+                    .collect::<Vec<_>>();
+                Some((
+                    span,
+                    format!("`{}` refers to {}", graph.name(i), list(&referred)),
+                ))
+            })
+            .collect::<Vec<(Span, String)>>();
+        let span = match labels.first() {
+            Some((span, _)) => span.clone(),
+            None => continue,
+        };
+
+        let names = cycle
+            .iter()
+            .map(|&i| format!("`{}`", graph.name(i)))
+            .collect::<Vec<_>>();
+        let productive = graph.has_abstraction(&cycle);
+        let message = match cycle.len() {
+            1 => format!("Recursive definition {}", names[0]),
+            _ => format!("Mutually recursive definitions {}", list(&names)),
+        };
+        let help = match (cycle.len(), productive) {
+            (1, true) => {
+                "strict strategies expand it forever: rewrite it with a fixed-point combinator for \
+                 call-by-value, no combinator helps applicative order"
+            }
+            (1, false) => {
+                "it only expands to itself, so evaluating it never ends under any strategy"
+            }
+            (_, true) => {
+                "strict strategies expand them forever: rewrite them with a fixed-point combinator \
+                 for call-by-value, no combinator helps applicative order"
+            }
+            (_, false) => {
+                "they only expand to each other, so evaluating them never ends under any strategy"
+            }
+        };
+
+        diagnostics.push(Diagnostic {
+            span,
+            severity: match productive {
+                true => Severity::Warning,
+                false => Severity::Error,
+            },
+            kind: DiagnosticKind::RecursiveDefinition { labels },
+            expected: Default::default(),
+            found: Some(graph.name(cycle[0]).to_string()),
+            message,
+            help: Some(help.to_string()),
+            fix: Vec::new(),
+        });
+    }
+
+    diagnostics
+}
+
+fn contains_abstraction(expr: &Expr) -> bool {
+    match expr {
+        Expr::Abstraction { .. } => true,
+        Expr::Application {
+            callee, argument, ..
+        } => contains_abstraction(callee) || contains_abstraction(argument),
+        Expr::Name { .. } | Expr::Error { .. } => false,
+    }
+}
+
+/// Joins `items` like `a, b and c`.
+fn list(items: &[String]) -> String {
+    match items {
+        [] => String::new(),
+        [only] => only.clone(),
+        [init @ .., last] => format!("{} and {}", init.join(", "), last),
+    }
+}
+
+/// A term `C` with `C f = f (C f)`, which turns a function taking itself into a recursive one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Combinator {
+    /// `λf.(λx.f (x x)) (λx.f (x x))`, for lazy strategies.
+    Y,
+    /// `λf.(λx.f (λv.x x v)) (λx.f (λv.x x v))`, for call-by-value. `x x` is hidden under `λv`
+    /// until the result is applied, which only helps strategies that don't reduce under `λ`.
+    Z,
+}
+
+impl Combinator {
+    /// The combinator that works under `strategy`, if any. Applicative order reduces the bodies
+    /// of abstractions, so it unfolds every fixed-point combinator forever.
+    pub fn for_strategy(strategy: Strategy) -> Option<Self> {
+        match strategy {
+            Strategy::Applicative => None,
+            Strategy::CallByValue => Some(Combinator::Z),
+            Strategy::Normal | Strategy::CallByName | Strategy::CallByNeed => Some(Combinator::Y),
+        }
+    }
+
+    pub fn expr(self) -> Expr {
+        let source = match self {
+            Combinator::Y => "λf.(λx.f (x x)) (λx.f (x x))",
+            Combinator::Z => "λf.(λx.f (λv.x x v)) (λx.f (λv.x x v))",
+        };
+        let mut expr = crate::parse(source).expect("the combinators parse");
+        expr.clear_spans();
+        expr
+    }
+}
+
+/// Rewrites every group of recursive definitions in `statements` with `combinator`, so that they
+/// no longer refer to each other. Groups without an abstraction, like `F := F`, are left alone,
+/// since they don't compute anything a combinator could unfold; [`check`] reports them.
+///
+/// `F := λx.F x` becomes `F := C (λf.λx.f x)`. A group of `n` definitions is turned into a
+/// single recursive function that picks one of them with a selector `λx1…xn.xi`, passing itself
+/// to the bodies, where the definitions are replaced by picking them from it.
+pub fn rewrite(statements: &mut [Statement], combinator: Combinator) {
+    let graph = Graph::new(statements);
+    let positions = statements
+        .iter()
+        .enumerate()
+        .filter(|(_, statement)| matches!(statement, Statement::Definition { .. }))
+        .map(|(position, _)| position)
+        .collect::<Vec<_>>();
+
+    let app = Expr::application;
+    let abs = |param: &str, body| Expr::abstraction(vec![Param::new(param)], body);
+
+    for cycle in graph.cycle_indices() {
+        if !graph.has_abstraction(&cycle) {
+            continue;
+        }
+
+        let names = cycle.iter().map(|&i| graph.name(i)).collect::<Vec<_>>();
+        let bodies = cycle
+            .iter()
+            .map(|&i| match &statements[positions[i]] {
+                Statement::Definition { expr, .. } => expr.clone(),
+                Statement::Expr(_) => unreachable!("positions are definitions"),
+            })
+            .collect::<Vec<_>>();
+        let mut used = bodies.iter().flat_map(free_vars).collect::<HashSet<_>>();
+
+        let fixed = if let [body] = &bodies[..] {
+            let f = fresh(&used);
+            let body = substitute(body.clone(), names[0], &Expr::name(&f));
+            vec![app(combinator.expr(), abs(&f, body))]
+        } else {
+            let r = fresh(&used);
+            used.insert(r.clone());
+            let s = fresh(&used);
+
+            let mut params = HashSet::new();
+            let params = (0..names.len())
+                .map(|_| {
+                    let param = fresh(&params);
+                    params.insert(param.clone());
+                    param
+                })
+                .collect::<Vec<_>>();
+            let selectors = params
+                .iter()
+                .map(|selected| {
+                    let params = params.iter().map(Param::new).collect();
+                    Expr::abstraction(params, Expr::name(selected))
+                })
+                .collect::<Vec<_>>();
+
+            let tuple = bodies.into_iter().fold(Expr::name(&s), |tuple, body| {
+                let body = names
+                    .iter()
+                    .zip(&selectors)
+                    .fold(body, |body, (name, selector)| {
+                        substitute(body, name, &app(Expr::name(&r), selector.clone()))
+                    });
+                app(tuple, body)
+            });
+            let fixed = app(combinator.expr(), abs(&r, abs(&s, tuple)));
+            selectors
+                .into_iter()
+                .map(|selector| app(fixed.clone(), selector))
+                .collect()
+        };
+
+        for (&i, fixed) in cycle.iter().zip(fixed) {
+            if let Statement::Definition { expr, .. } = &mut statements[positions[i]] {
+                *expr = fixed;
+            }
+        }
+    }
+}
@@ -0,0 +1,395 @@
+use crate::lint::Lint;
+use ariadne::{Color, Fmt, Label, Report, ReportKind, Source};
+use chumsky::error::{Simple, SimpleReason};
+use std::collections::BTreeSet;
+use std::fmt::Display;
+use std::io::Write;
+use std::ops::Range;
+
+/// A range of byte offsets into the source.
+pub type Span = Range<usize>;
+
+/// A problem found in the source, ready to be rendered with [`report`] or [`to_json`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub span: Span,
+    pub severity: Severity,
+    pub kind: DiagnosticKind,
+    /// What would have been accepted at `span`. `None` stands for the end of input.
+    pub expected: BTreeSet<Option<String>>,
+    /// What was found at `span` instead, `None` if the input ended.
+    pub found: Option<String>,
+    pub message: String,
+    /// A suggestion on how to fix the problem.
+    pub help: Option<String>,
+    /// Edits to the source that carry out `help`, empty if it can't be done mechanically. See
+    /// [`apply_fixes`].
+    pub fix: Vec<Edit>,
+}
+
+/// Replaces the source at `span` with `replacement`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Edit {
+    pub span: Span,
+    pub replacement: String,
+}
+
+impl Diagnostic {
+    pub fn is_error(&self) -> bool {
+        self.severity == Severity::Error
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    /// The program can't be run.
+    Error,
+    /// The program runs, but probably not as intended.
+    Warning,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DiagnosticKind {
+    /// The delimiter opened at `span` was not closed before the diagnostic's span.
+    UnclosedDelimiter {
+        delimiter: String,
+        span: Span,
+    },
+    UnexpectedToken,
+    /// Characters that don't form any token.
+    InvalidCharacter,
+    /// A word that isn't a valid name, like `x1` or `Foo`.
+    InvalidName,
+    /// An integer literal larger than its numerals can hold.
+    NumberTooLarge,
+    /// A block comment opened at the span that is never closed.
+    UnterminatedComment,
+    /// A global name that isn't defined.
+    UndefinedName,
+    /// A variable that isn't bound by any enclosing abstraction.
+    FreeVariable,
+    /// Definitions that refer to each other in a cycle, with a label for each of them.
+    RecursiveDefinition {
+        labels: Vec<(Span, String)>,
+    },
+    /// Code that works, but likely has a mistake or could be simpler. `related` points at a
+    /// second place involved, like the parameter that is shadowed.
+    Lint {
+        lint: Lint,
+        related: Option<Span>,
+    },
+    Custom,
+}
+
+impl<T: Display + std::hash::Hash + Eq> From<Simple<T>> for Diagnostic {
+    fn from(error: Simple<T>) -> Self {
+        let expected = error
+            .expected()
+            .map(|expected| expected.as_ref().map(ToString::to_string))
+            .collect::<BTreeSet<_>>();
+        let found = error.found().map(ToString::to_string);
+
+        let (kind, message) = match error.reason() {
+            SimpleReason::Unclosed { span, delimiter } => (
+                DiagnosticKind::UnclosedDelimiter {
+                    delimiter: delimiter.to_string(),
+                    span: span.clone(),
+                },
+                format!("Unclosed delimiter {}", delimiter),
+            ),
+            SimpleReason::Unexpected => {
+                let expected = match expected.is_empty() {
+                    true => "something else".to_string(),
+                    false => expected
+                        .iter()
+                        .map(|expected| expected.as_deref().unwrap_or("end of input"))
+                        .collect::<Vec<_>>()
+                        .join(", "),
+                };
+                let message = match found {
+                    Some(_) => format!("Unexpected token in input, expected {}", expected),
+                    None => format!("Unexpected end of input, expected {}", expected),
+                };
+                (DiagnosticKind::UnexpectedToken, message)
+            }
+            SimpleReason::Custom(message) => (DiagnosticKind::Custom, message.clone()),
+        };
+
+        Diagnostic {
+            span: error.span(),
+            severity: Severity::Error,
+            kind,
+            expected,
+            found,
+            message,
+            help: None,
+            fix: Vec::new(),
+        }
+    }
+}
+
+/// Builds an ariadne report for `diagnostic` in `input`, naming the source `source_id`.
+pub fn report(
+    diagnostic: &Diagnostic,
+    source_id: &str,
+    input: &str,
+) -> Report<(String, Range<usize>)> {
+    // ariadne counts chars, spans count bytes
+    let located = |span: &Span| (source_id.to_string(), chars(input, span));
+    let span = located(&diagnostic.span);
+    let found = diagnostic.found.as_deref().unwrap_or("end of file");
+
+    let (kind, color) = match diagnostic.severity {
+        Severity::Error => (ReportKind::Error, Color::Red),
+        Severity::Warning => (ReportKind::Warning, Color::Yellow),
+    };
+    let report = Report::build(kind, source_id, span.1.start).with_message(&diagnostic.message);
+
+    let report = match &diagnostic.kind {
+        DiagnosticKind::UnclosedDelimiter {
+            delimiter,
+            span: opened,
+        } => report
+            .with_label(
+                Label::new(located(opened))
+                    .with_message(format!(
+                        "Unclosed delimiter {}",
+                        delimiter.fg(Color::Yellow)
+                    ))
+                    .with_color(Color::Yellow),
+            )
+            .with_label(
+                Label::new(span)
+                    .with_message(format!(
+                        "Must be closed before this {}",
+                        found.fg(Color::Red)
+                    ))
+                    .with_color(Color::Red),
+            ),
+        DiagnosticKind::UnexpectedToken => report.with_label(
+            Label::new(span)
+                .with_message(format!("Unexpected token {}", found.fg(Color::Red)))
+                .with_color(Color::Red),
+        ),
+        DiagnosticKind::InvalidCharacter => report.with_label(
+            Label::new(span)
+                .with_message(format!("{} is not part of any token", found.fg(Color::Red)))
+                .with_color(Color::Red),
+        ),
+        DiagnosticKind::InvalidName => report.with_label(
+            Label::new(span)
+                .with_message(format!("{} is not a single name", found.fg(Color::Red)))
+                .with_color(Color::Red),
+        ),
+        DiagnosticKind::NumberTooLarge => report.with_label(
+            Label::new(span)
+                .with_message(format!(
+                    "{} doesn't fit into a numeral",
+                    found.fg(Color::Red)
+                ))
+                .with_color(Color::Red),
+        ),
+        DiagnosticKind::UnterminatedComment => report.with_label(
+            Label::new(span)
+                .with_message("This comment is never closed")
+                .with_color(Color::Red),
+        ),
+        DiagnosticKind::UndefinedName => report.with_label(
+            Label::new(span)
+                .with_message(format!("{} is not defined", found.fg(color)))
+                .with_color(color),
+        ),
+        DiagnosticKind::FreeVariable => report.with_label(
+            Label::new(span)
+                .with_message(format!("{} is not bound by any λ", found.fg(color)))
+                .with_color(color),
+        ),
+        DiagnosticKind::RecursiveDefinition { labels } => {
+            labels.iter().fold(report, |report, (span, message)| {
+                report.with_label(
+                    Label::new(located(span))
+                        .with_message(message)
+                        .with_color(color),
+                )
+            })
+        }
+        DiagnosticKind::Lint { lint, related } => {
+            let report = report.with_label(
+                Label::new(span)
+                    .with_message(lint.label().fg(color))
+                    .with_color(color),
+            );
+            match related {
+                Some(related) => report.with_label(
+                    Label::new(located(related))
+                        .with_message(lint.related_label())
+                        .with_color(Color::Cyan),
+                ),
+                None => report,
+            }
+        }
+        DiagnosticKind::Custom => report.with_label(
+            Label::new(span)
+                .with_message(format!("{}", diagnostic.message.as_str().fg(color)))
+                .with_color(color),
+        ),
+    };
+
+    match &diagnostic.help {
+        Some(help) => report.with_note(help),
+        None => report,
+    }
+    .finish()
+}
+
+/// Writes the ariadne reports of all `diagnostics` to `out`.
+pub fn write(
+    diagnostics: &[Diagnostic],
+    source_id: &str,
+    input: &str,
+    mut out: impl Write,
+) -> std::io::Result<()> {
+    let mut cache = (source_id.to_string(), Source::from(input));
+    for diagnostic in diagnostics {
+        report(diagnostic, source_id, input).write(&mut cache, &mut out)?;
+    }
+    Ok(())
+}
+
+/// Applies the fixes of `diagnostics` to `input`. Fixes whose edits overlap an earlier fix are
+/// left out, running the diagnostics again after applying the rest finds them again.
+pub fn apply_fixes(input: &str, diagnostics: &[Diagnostic]) -> String {
+    let mut edits = Vec::<&Edit>::new();
+    for diagnostic in diagnostics {
+        let overlaps = diagnostic.fix.iter().any(|edit| {
+            edits
+                .iter()
+                .any(|other| edit.span.start < other.span.end && other.span.start < edit.span.end)
+        });
+        if !overlaps {
+            edits.extend(&diagnostic.fix);
+        }
+    }
+    edits.sort_by_key(|edit| edit.span.start);
+
+    let mut fixed = String::new();
+    let mut end = 0;
+    for edit in edits {
+        fixed.push_str(&input[end..edit.span.start]);
+        fixed.push_str(&edit.replacement);
+        end = edit.span.end;
+    }
+    fixed.push_str(&input[end..]);
+    fixed
+}
+
+/// Renders `diagnostics` as a JSON array, one object per diagnostic with its fields. Spans are
+/// objects with `start` and `end` byte offsets.
+pub fn to_json(diagnostics: &[Diagnostic], source_id: &str) -> String {
+    let span = |span: &Span| format!(r#"{{"start":{},"end":{}}}"#, span.start, span.end);
+    let string = |string: Option<&str>| string.map_or("null".to_string(), json_string);
+
+    let objects = diagnostics.iter().map(|diagnostic| {
+        let kind = match &diagnostic.kind {
+            DiagnosticKind::UnclosedDelimiter {
+                delimiter,
+                span: opened,
+            } => format!(
+                r#"{{"type":"unclosed_delimiter","delimiter":{},"span":{}}}"#,
+                json_string(delimiter),
+                span(opened)
+            ),
+            DiagnosticKind::UnexpectedToken => r#"{"type":"unexpected_token"}"#.to_string(),
+            DiagnosticKind::InvalidCharacter => r#"{"type":"invalid_character"}"#.to_string(),
+            DiagnosticKind::InvalidName => r#"{"type":"invalid_name"}"#.to_string(),
+            DiagnosticKind::NumberTooLarge => r#"{"type":"number_too_large"}"#.to_string(),
+            DiagnosticKind::UnterminatedComment => {
+                r#"{"type":"unterminated_comment"}"#.to_string()
+            }
+            DiagnosticKind::UndefinedName => r#"{"type":"undefined_name"}"#.to_string(),
+            DiagnosticKind::FreeVariable => r#"{"type":"free_variable"}"#.to_string(),
+            DiagnosticKind::RecursiveDefinition { labels } => {
+                let labels = labels.iter().map(|(label, message)| {
+                    format!(
+                        r#"{{"span":{},"message":{}}}"#,
+                        span(label),
+                        json_string(message)
+                    )
+                });
+                format!(
+                    r#"{{"type":"recursive_definition","labels":[{}]}}"#,
+                    labels.collect::<Vec<_>>().join(",")
+                )
+            }
+            DiagnosticKind::Lint { lint, related } => format!(
+                r#"{{"type":"lint","lint":"{}","related":{}}}"#,
+                lint,
+                related.as_ref().map_or("null".to_string(), span)
+            ),
+            DiagnosticKind::Custom => r#"{"type":"custom"}"#.to_string(),
+        };
+        let expected = diagnostic
+            .expected
+            .iter()
+            .map(|expected| string(expected.as_deref()))
+            .collect::<Vec<_>>();
+        let fix = diagnostic
+            .fix
+            .iter()
+            .map(|edit| {
+                format!(
+                    r#"{{"span":{},"replacement":{}}}"#,
+                    span(&edit.span),
+                    json_string(&edit.replacement)
+                )
+            })
+            .collect::<Vec<_>>();
+
+        let severity = match diagnostic.severity {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        };
+
+        format!(
+            r#"{{"source":{},"span":{},"kind":{},"severity":"{}","expected":[{}],"found":{},"message":{},"help":{},"fix":[{}]}}"#,
+            json_string(source_id),
+            span(&diagnostic.span),
+            kind,
+            severity,
+            expected.join(","),
+            string(diagnostic.found.as_deref()),
+            json_string(&diagnostic.message),
+            string(diagnostic.help.as_deref()),
+            fix.join(",")
+        )
+    });
+
+    format!("[{}]", objects.collect::<Vec<_>>().join(","))
+}
+
+fn json_string(string: &str) -> String {
+    let mut json = String::from('"');
+    for c in string.chars() {
+        match c {
+            '"' => json.push_str(r#"\""#),
+            '\\' => json.push_str(r"\\"),
+            '\n' => json.push_str(r"\n"),
+            '\r' => json.push_str(r"\r"),
+            '\t' => json.push_str(r"\t"),
+            c if c.is_control() => json.push_str(&format!(r"\u{:04x}", c as u32)),
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+    json
+}
+
+/// Converts a span of byte offsets into `input` to char offsets. Offsets past the end, like the
+/// span of the end of input, stay past the end.
+fn chars(input: &str, span: &Span) -> Range<usize> {
+    let char_offset = |byte: usize| match input.get(..byte) {
+        Some(prefix) => prefix.chars().count(),
+        None => input.chars().count() + byte.saturating_sub(input.len()),
+    };
+    char_offset(span.start)..char_offset(span.end)
+}
@@ -0,0 +1,493 @@
+use crate::debruijn::Term;
+use crate::parser::{Direction, Expr, Param};
+use std::collections::{BTreeMap, HashSet};
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+mod need;
+
+/// The global definitions made with `NAME := expr`.
+#[derive(Debug, Clone, Default)]
+pub struct Env {
+    definitions: BTreeMap<String, Expr>,
+}
+
+impl Env {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Binds `name` to `expr`, replacing any previous definition.
+    ///
+    /// The spans of `expr` are dropped, since the definition ends up inside other terms whose
+    /// spans refer to a different source.
+    pub fn define(&mut self, name: String, mut expr: Expr) {
+        expr.clear_spans();
+        self.definitions.insert(name, expr);
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Expr> {
+        self.definitions.get(name)
+    }
+
+    /// Iterates over all definitions, ordered by name.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &Expr)> {
+        self.definitions
+            .iter()
+            .map(|(name, expr)| (name.as_str(), expr))
+    }
+}
+
+/// The order in which redexes are contracted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Strategy {
+    /// Leftmost-outermost first, also under abstractions. Finds the normal form if there is one.
+    #[default]
+    Normal,
+    /// Leftmost-innermost first, so arguments are normalized before they are substituted.
+    Applicative,
+    /// Leftmost-outermost, but only along the head of the term, stopping at weak head normal form.
+    CallByName,
+    /// Callee and argument are reduced to values before substituting, but nothing is reduced under
+    /// abstractions.
+    CallByValue,
+    /// Like call-by-name, but every argument is evaluated at most once and shared between its uses.
+    CallByNeed,
+}
+
+impl Strategy {
+    /// Whether a redex is contracted before the redexes inside of it.
+    fn outermost(self) -> bool {
+        matches!(
+            self,
+            Strategy::Normal | Strategy::CallByName | Strategy::CallByNeed
+        )
+    }
+
+    /// Whether reduction stops at abstractions instead of continuing in their body.
+    fn weak(self) -> bool {
+        !matches!(self, Strategy::Normal | Strategy::Applicative)
+    }
+
+    fn reduces_arguments(self) -> bool {
+        !matches!(self, Strategy::CallByName | Strategy::CallByNeed)
+    }
+}
+
+impl FromStr for Strategy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "normal" => Ok(Strategy::Normal),
+            "applicative" => Ok(Strategy::Applicative),
+            "name" | "call-by-name" => Ok(Strategy::CallByName),
+            "value" | "call-by-value" => Ok(Strategy::CallByValue),
+            "need" | "call-by-need" => Ok(Strategy::CallByNeed),
+            _ => Err(format!(
+                "unknown strategy `{}`, expected one of normal, applicative, call-by-name, \
+                 call-by-value or call-by-need",
+                s
+            )),
+        }
+    }
+}
+
+/// Bounds on a reduction, so that terms without a normal form can't hang the caller. Nothing is
+/// limited by default.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Limits {
+    /// The maximum number of beta steps. Expanding a global counts as a step too, so that
+    /// definitions like `F := F` run out of fuel.
+    pub fuel: Option<usize>,
+    /// The maximum wall-clock time, checked between steps.
+    pub timeout: Option<Duration>,
+    /// The maximum number of nodes the term may grow to. Call-by-need doesn't build the
+    /// intermediate terms, so it counts the arguments waiting to be applied instead.
+    pub max_size: Option<usize>,
+}
+
+impl Limits {
+    /// Returns the limit exceeded by a reduction that took `steps` steps since `start` and arrived
+    /// at a term with `size` nodes, if any.
+    fn exceeded(
+        &self,
+        steps: usize,
+        start: Instant,
+        size: impl FnOnce() -> usize,
+    ) -> Option<Limit> {
+        self.fuel
+            .filter(|&fuel| steps >= fuel)
+            .map(Limit::Fuel)
+            .or_else(|| {
+                self.timeout
+                    .filter(|&timeout| start.elapsed() > timeout)
+                    .map(Limit::Timeout)
+            })
+            .or_else(|| {
+                self.max_size
+                    .filter(|&max_size| size() > max_size)
+                    .map(Limit::Size)
+            })
+    }
+}
+
+/// The limit that made a reduction give up, with its configured value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Limit {
+    Fuel(usize),
+    Timeout(Duration),
+    Size(usize),
+}
+
+/// A reduction that was stopped by one of its [`Limits`] before it was done.
+#[derive(Debug, Clone)]
+pub struct GaveUp {
+    pub limit: Limit,
+    /// The term reached before giving up.
+    pub expr: Expr,
+    pub steps: usize,
+}
+
+impl Display for GaveUp {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "gave up after {} steps: ", self.steps)?;
+        match self.limit {
+            Limit::Fuel(fuel) => write!(f, "ran out of fuel ({} steps)", fuel),
+            Limit::Timeout(timeout) => write!(f, "took longer than {:?}", timeout),
+            Limit::Size(size) => write!(f, "the term grew beyond {} nodes", size),
+        }
+    }
+}
+
+/// Reduces `expr` to normal form using normal order (leftmost-outermost) reduction.
+///
+/// Globals from `env` are expanded when the reduction reaches them. Returns the normal form
+/// together with the number of beta steps taken. Terms without a normal form make this loop
+/// forever.
+pub fn reduce(expr: Expr, env: &Env) -> (Expr, usize) {
+    match reduce_with(expr, env, Strategy::Normal, Limits::default(), |_, _| {}) {
+        Ok(reduced) => reduced,
+        Err(_) => unreachable!("reduction without limits gave up"),
+    }
+}
+
+/// Like [`reduce`], but contracts redexes in the order given by `strategy`, gives up once one of
+/// the `limits` is exceeded, and calls `on_step` with the current term and the path to its redex
+/// before every beta step.
+///
+/// The weak strategies stop at weak head normal form. Call-by-need shares arguments instead of
+/// copying them into the term, so it never calls `on_step`.
+pub fn reduce_with(
+    mut expr: Expr,
+    env: &Env,
+    strategy: Strategy,
+    limits: Limits,
+    mut on_step: impl FnMut(&Expr, &[Direction]),
+) -> Result<(Expr, usize), GaveUp> {
+    let start = Instant::now();
+
+    if strategy == Strategy::CallByNeed {
+        return match need::reduce(&Term::from(&expr), env, limits, start) {
+            Ok((term, steps)) => Ok((Expr::from(&term), steps)),
+            Err((limit, term, steps)) => Err(GaveUp {
+                limit,
+                expr: Expr::from(&term),
+                steps,
+            }),
+        };
+    }
+
+    let mut steps = 0;
+    let mut expansions = 0;
+    loop {
+        let mut check = |size| {
+            let limit = limits.exceeded(steps + expansions, start, || size);
+            expansions += 1;
+            limit
+        };
+        let path = match search(&mut expr, env, strategy, &mut check) {
+            Ok(Some(path)) => path,
+            Ok(None) => break,
+            Err(limit) => return Err(GaveUp { limit, expr, steps }),
+        };
+        if let Some(limit) = limits.exceeded(steps + expansions, start, || expr.size()) {
+            return Err(GaveUp { limit, expr, steps });
+        }
+
+        on_step(&expr, &path);
+        contract_at(&mut expr, &path);
+        steps += 1;
+    }
+
+    // a term can be too large without having a redex, like a big numeral
+    match limits.max_size.filter(|&max_size| expr.size() > max_size) {
+        Some(max_size) => Err(GaveUp {
+            limit: Limit::Size(max_size),
+            expr,
+            steps,
+        }),
+        None => Ok((expr, steps)),
+    }
+}
+
+/// Contracts the next redex of `expr` according to `strategy` in place, expanding globals on the
+/// way.
+///
+/// Returns `false` if there is nothing left to reduce.
+pub fn step(expr: &mut Expr, env: &Env, strategy: Strategy) -> bool {
+    match find_redex(expr, env, strategy) {
+        Some(path) => {
+            contract_at(expr, &path);
+            true
+        }
+        None => false,
+    }
+}
+
+/// Returns the path to the redex of `expr` that `strategy` contracts next, expanding the globals
+/// that stand in the way of finding it. Call-by-need picks the same redex as call-by-name.
+///
+/// Globals that only expand to each other, like `F := F`, make this loop forever.
+pub fn find_redex(expr: &mut Expr, env: &Env, strategy: Strategy) -> Option<Vec<Direction>> {
+    match search(expr, env, strategy, &mut |_| None) {
+        Ok(path) => path,
+        Err(_) => unreachable!("search without limits gave up"),
+    }
+}
+
+/// Like [`find_redex`], but calls `check` with the size the term grows to before every expansion
+/// of a global, and gives up with the limit it returns.
+fn search(
+    expr: &mut Expr,
+    env: &Env,
+    strategy: Strategy,
+    check: &mut dyn FnMut(usize) -> Option<Limit>,
+) -> Result<Option<Vec<Direction>>, Limit> {
+    let mut search = Search {
+        env,
+        strategy,
+        bound: Vec::new(),
+        path: Vec::new(),
+        size: expr.size(),
+        check,
+    };
+    Ok(search.find_in(expr)?.then_some(search.path))
+}
+
+struct Search<'s> {
+    env: &'s Env,
+    strategy: Strategy,
+    bound: Vec<String>,
+    path: Vec<Direction>,
+    /// The size of the whole term, which grows with every expansion.
+    size: usize,
+    check: &'s mut dyn FnMut(usize) -> Option<Limit>,
+}
+
+impl Search<'_> {
+    fn find_in(&mut self, expr: &mut Expr) -> Result<bool, Limit> {
+        match expr {
+            Expr::Application {
+                callee, argument, ..
+            } => {
+                while self.expand(callee)? {}
+
+                let redex = matches!(**callee, Expr::Abstraction { .. });
+                if redex && self.strategy.outermost() {
+                    return Ok(true);
+                }
+
+                self.path.push(Direction::Callee);
+                if self.find_in(callee)? {
+                    return Ok(true);
+                }
+                self.path.pop();
+
+                if self.strategy.reduces_arguments() {
+                    self.path.push(Direction::Argument);
+                    if self.find_in(argument)? {
+                        return Ok(true);
+                    }
+                    self.path.pop();
+                }
+
+                Ok(redex)
+            }
+            Expr::Abstraction { .. } if self.strategy.weak() => Ok(false),
+            Expr::Abstraction { params, body, .. } => {
+                let len = self.bound.len();
+                self.bound
+                    .extend(params.iter().map(|param| param.name.clone()));
+                self.path.push(Direction::Body);
+                let found = self.find_in(body)?;
+                if !found {
+                    self.path.pop();
+                }
+                self.bound.truncate(len);
+                Ok(found)
+            }
+            Expr::Name { .. } => {
+                while self.expand(expr)? {}
+                match expr {
+                    Expr::Name { .. } => Ok(false),
+                    _ => self.find_in(expr),
+                }
+            }
+            Expr::Error { .. } => Ok(false),
+        }
+    }
+
+    /// Replaces `expr` with its definition if it is a global that isn't shadowed by a binder.
+    fn expand(&mut self, expr: &mut Expr) -> Result<bool, Limit> {
+        let definition = match expr {
+            Expr::Name { name, .. } if !self.bound.contains(name) => self.env.get(name),
+            _ => None,
+        };
+
+        match definition {
+            Some(definition) => {
+                if let Some(limit) = (self.check)(self.size) {
+                    return Err(limit);
+                }
+                self.size += definition.size() - 1;
+                *expr = definition.clone();
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+}
+
+/// Contracts the redex at `path`, as returned by [`find_redex`].
+pub fn contract_at(expr: &mut Expr, path: &[Direction]) {
+    let redex = expr.get_mut(path).expect("no redex at path");
+    let taken = std::mem::replace(redex, Expr::name(""));
+    *redex = contract(taken);
+}
+
+fn contract(redex: Expr) -> Expr {
+    match redex {
+        Expr::Application {
+            callee, argument, ..
+        } => match *callee {
+            Expr::Abstraction { params, body, .. } => {
+                let (param, inner) = peel(params, *body);
+                substitute(inner, &param.name, &argument)
+            }
+            _ => unreachable!("contracted an application without an abstraction as callee"),
+        },
+        _ => unreachable!("contracted a term that is not an application"),
+    }
+}
+
+/// Splits `λab.body` into its first parameter and the remaining `λb.body`.
+fn peel(params: Vec<Param>, body: Expr) -> (Param, Expr) {
+    let (param, rest) = params
+        .split_first()
+        .expect("abstraction without parameters");
+
+    let inner = if rest.is_empty() {
+        body
+    } else {
+        Expr::abstraction(rest.to_vec(), body)
+    };
+
+    (param.clone(), inner)
+}
+
+/// Replaces every free occurrence of `name` in `expr` with `value`, renaming binders of `expr`
+/// that would otherwise capture free variables of `value`.
+pub fn substitute(expr: Expr, name: &str, value: &Expr) -> Expr {
+    match expr {
+        Expr::Name { name: ident, .. } if ident == name => value.clone(),
+        Expr::Name { name, span } => Expr::Name { name, span },
+        Expr::Error { span } => Expr::Error { span },
+        Expr::Application {
+            callee,
+            argument,
+            span,
+        } => Expr::Application {
+            callee: Box::new(substitute(*callee, name, value)),
+            argument: Box::new(substitute(*argument, name, value)),
+            span,
+        },
+        Expr::Abstraction { params, body, span } => {
+            let merge = params.len() > 1;
+            let (mut param, mut inner) = peel(params, *body);
+
+            if param.name != name && free_vars(&inner).contains(name) {
+                let value_free = free_vars(value);
+                if value_free.contains(&param.name) {
+                    let mut used = value_free;
+                    used.extend(free_vars(&inner));
+                    used.insert(name.to_string());
+                    let renamed = fresh(&used);
+                    inner = substitute(inner, &param.name, &Expr::name(renamed.clone()));
+                    param = Param::new(renamed);
+                }
+                inner = substitute(inner, name, value);
+            }
+
+            match inner {
+                Expr::Abstraction {
+                    mut params, body, ..
+                } if merge => {
+                    params.insert(0, param);
+                    Expr::Abstraction { params, body, span }
+                }
+                inner => Expr::Abstraction {
+                    params: vec![param],
+                    body: Box::new(inner),
+                    span,
+                },
+            }
+        }
+    }
+}
+
+/// Collects the names occurring free in `expr`.
+pub fn free_vars(expr: &Expr) -> HashSet<String> {
+    fn collect(expr: &Expr, bound: &mut Vec<String>, free: &mut HashSet<String>) {
+        match expr {
+            Expr::Name { name, .. } => {
+                if !bound.contains(name) {
+                    free.insert(name.clone());
+                }
+            }
+            Expr::Application {
+                callee, argument, ..
+            } => {
+                collect(callee, bound, free);
+                collect(argument, bound, free);
+            }
+            Expr::Abstraction { params, body, .. } => {
+                let len = bound.len();
+                bound.extend(params.iter().map(|param| param.name.clone()));
+                collect(body, bound, free);
+                bound.truncate(len);
+            }
+            Expr::Error { .. } => {}
+        }
+    }
+
+    let mut free = HashSet::new();
+    collect(expr, &mut Vec::new(), &mut free);
+    free
+}
+
+/// Picks a variable name that is not in `used`, preferring single lowercase letters, which are
+/// valid in either syntax. Once all of those are used, the letters continue with a digit, as in
+/// `a1`, which only parses back with long names: without them there are no more variables.
+pub(crate) fn fresh(used: &HashSet<String>) -> String {
+    (0..)
+        .flat_map(|n: usize| {
+            ('a'..='z').map(move |letter| match n {
+                0 => letter.to_string(),
+                n => format!("{letter}{n}"),
+            })
+        })
+        .find(|name| !used.contains(name))
+        .expect("there are infinitely many names")
+}
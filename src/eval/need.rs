@@ -0,0 +1,297 @@
+use super::{Env, Limit, Limits};
+use crate::debruijn::Term;
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::time::Instant;
+
+/// Reduces `term` to weak head normal form with call-by-need, returning it together with the
+/// number of beta steps taken.
+///
+/// Arguments become thunks that are shared between all uses of a variable and overwritten with
+/// their value the first time they are forced. If one of the `limits` is exceeded, the term
+/// reached so far is returned with the limit instead, abbreviated to a few nodes.
+pub(super) fn reduce(
+    term: &Term,
+    env: &Env,
+    limits: Limits,
+    start: Instant,
+) -> Result<(Term, usize), (Limit, Term, usize)> {
+    let mut machine = Machine {
+        env,
+        limits,
+        start,
+        steps: 0,
+        expansions: 0,
+    };
+    let closure = Closure {
+        term: term.clone(),
+        scope: Scope::default(),
+    };
+
+    match machine.whnf(closure, Vec::new()) {
+        Ok((head, stack)) => {
+            let value = apply(head, stack);
+            let max_size = limits.max_size.unwrap_or(usize::MAX);
+            let mut read_back = ReadBack::new(max_size);
+            let term = read_back.term(&value.term, &value.scope, 0);
+            match read_back.truncated {
+                false => Ok((term, machine.steps)),
+                true => Err((Limit::Size(max_size), term, machine.steps)),
+            }
+        }
+        Err(Stopped {
+            limit,
+            closure,
+            stack,
+        }) => {
+            let value = apply(closure, stack);
+            let term = ReadBack::new(ABBREVIATED_SIZE).term(&value.term, &value.scope, 0);
+            Err((limit, term, machine.steps))
+        }
+    }
+}
+
+/// How many nodes of the term are read back after giving up.
+const ABBREVIATED_SIZE: usize = 1000;
+
+/// A term together with the thunks for its free de Bruijn indices.
+#[derive(Clone)]
+struct Closure {
+    term: Term,
+    scope: Scope,
+}
+
+#[derive(Clone)]
+enum Thunk {
+    Delayed(Closure),
+    /// Evaluated to weak head normal form.
+    Forced(Closure),
+}
+
+type SharedThunk = Rc<RefCell<Thunk>>;
+
+/// An immutable list of thunks, indexed from the innermost binder outwards.
+#[derive(Clone, Default)]
+struct Scope(Option<Rc<(SharedThunk, Scope)>>);
+
+impl Scope {
+    fn push(&self, thunk: SharedThunk) -> Scope {
+        Scope(Some(Rc::new((thunk, self.clone()))))
+    }
+
+    fn get(&self, index: usize) -> &SharedThunk {
+        let (thunk, outer) = &**self.0.as_ref().expect("de Bruijn index without a binder");
+        match index {
+            0 => thunk,
+            _ => outer.get(index - 1),
+        }
+    }
+}
+
+struct Machine<'env> {
+    env: &'env Env,
+    limits: Limits,
+    start: Instant,
+    steps: usize,
+    /// How many globals were replaced with their definition, which counts toward the fuel.
+    expansions: usize,
+}
+
+/// The state of the machine when one of the limits stopped it.
+struct Stopped {
+    limit: Limit,
+    closure: Closure,
+    stack: Vec<SharedThunk>,
+}
+
+impl Machine<'_> {
+    /// Evaluates `closure` applied to the arguments on `stack` (the last one is applied first)
+    /// until it is an abstraction without arguments left, or stuck on a free variable.
+    fn whnf(
+        &mut self,
+        mut closure: Closure,
+        mut stack: Vec<SharedThunk>,
+    ) -> Result<(Closure, Vec<SharedThunk>), Stopped> {
+        loop {
+            closure = match closure.term {
+                Term::App(callee, argument) => {
+                    stack.push(Rc::new(RefCell::new(Thunk::Delayed(Closure {
+                        term: *argument,
+                        scope: closure.scope.clone(),
+                    }))));
+                    Closure {
+                        term: *callee,
+                        scope: closure.scope,
+                    }
+                }
+                Term::Abs(body) if stack.is_empty() => {
+                    let term = Term::Abs(body);
+                    return Ok((Closure { term, ..closure }, stack));
+                }
+                Term::Abs(body) => {
+                    if let Some(limit) = self.exceeded(stack.len()) {
+                        let term = Term::Abs(body);
+                        let closure = Closure { term, ..closure };
+                        return Err(Stopped {
+                            limit,
+                            closure,
+                            stack,
+                        });
+                    }
+
+                    self.steps += 1;
+                    let argument = stack.pop().expect("stack checked to be non-empty");
+                    Closure {
+                        term: *body,
+                        scope: closure.scope.push(argument),
+                    }
+                }
+                Term::Var(index) => {
+                    let thunk = closure.scope.get(index).clone();
+                    match self.force(&thunk) {
+                        Ok(value) => value,
+                        Err(limit) => {
+                            let term = Term::Var(index);
+                            let closure = Closure { term, ..closure };
+                            return Err(Stopped {
+                                limit,
+                                closure,
+                                stack,
+                            });
+                        }
+                    }
+                }
+                Term::Free(name) => match self.env.get(&name) {
+                    Some(definition) => {
+                        if let Some(limit) = self.exceeded(stack.len()) {
+                            let term = Term::Free(name);
+                            let closure = Closure { term, ..closure };
+                            return Err(Stopped {
+                                limit,
+                                closure,
+                                stack,
+                            });
+                        }
+
+                        self.expansions += 1;
+                        Closure {
+                            term: Term::from(definition),
+                            scope: Scope::default(),
+                        }
+                    }
+                    None => {
+                        let term = Term::Free(name);
+                        return Ok((Closure { term, ..closure }, stack));
+                    }
+                },
+            };
+        }
+    }
+
+    /// Returns the limit exceeded with `waiting` arguments on the stack, if any.
+    fn exceeded(&self, waiting: usize) -> Option<Limit> {
+        self.limits
+            .exceeded(self.steps + self.expansions, self.start, || waiting)
+    }
+
+    /// Evaluates `thunk` unless that already happened. A thunk whose evaluation is stopped by a
+    /// limit stays delayed.
+    fn force(&mut self, thunk: &SharedThunk) -> Result<Closure, Limit> {
+        let delayed = match &*thunk.borrow() {
+            Thunk::Forced(value) => return Ok(value.clone()),
+            Thunk::Delayed(closure) => closure.clone(),
+        };
+
+        let (head, stack) = self
+            .whnf(delayed, Vec::new())
+            .map_err(|stopped| stopped.limit)?;
+        let value = apply(head, stack);
+        *thunk.borrow_mut() = Thunk::Forced(value.clone());
+        Ok(value)
+    }
+}
+
+/// Turns a head and the arguments still waiting on the stack back into a single closure, binding
+/// the argument thunks in its scope so they stay shared.
+fn apply(head: Closure, stack: Vec<SharedThunk>) -> Closure {
+    let count = stack.len();
+    // the head's own indices have to skip the argument thunks bound in front of them
+    let mut term = shift(&head.term, count, 0);
+    let mut scope = head.scope;
+
+    // the first argument is on top of the stack and ends up bound outermost
+    for (position, argument) in stack.into_iter().rev().enumerate() {
+        term = Term::App(Box::new(term), Box::new(Term::Var(count - 1 - position)));
+        scope = scope.push(argument);
+    }
+
+    Closure { term, scope }
+}
+
+/// Adds `by` to every index of `term` that points outside of the `depth` innermost binders.
+fn shift(term: &Term, by: usize, depth: usize) -> Term {
+    match term {
+        Term::Var(index) if *index >= depth => Term::Var(index + by),
+        Term::Var(index) => Term::Var(*index),
+        Term::Free(name) => Term::Free(name.clone()),
+        Term::Abs(body) => Term::Abs(Box::new(shift(body, by, depth + 1))),
+        Term::App(callee, argument) => Term::App(
+            Box::new(shift(callee, by, depth)),
+            Box::new(shift(argument, by, depth)),
+        ),
+    }
+}
+
+/// Turns closures back into plain terms by substituting the thunks of their scope.
+///
+/// A thunk is copied into every place that uses it, so the term can be exponentially larger than
+/// the machine state. Once `budget` nodes are built, the rest is replaced by `…`.
+struct ReadBack {
+    budget: usize,
+    truncated: bool,
+}
+
+impl ReadBack {
+    fn new(budget: usize) -> Self {
+        Self {
+            budget,
+            truncated: false,
+        }
+    }
+
+    /// Reads back `term`, which sits below `depth` binders of its own inside `scope`.
+    fn term(&mut self, term: &Term, scope: &Scope, depth: usize) -> Term {
+        if self.budget == 0 {
+            self.truncated = true;
+            return Term::Free("…".to_string());
+        }
+
+        match term {
+            Term::Var(index) if *index >= depth => {
+                let closure = match &*scope.get(index - depth).borrow() {
+                    Thunk::Delayed(closure) | Thunk::Forced(closure) => closure.clone(),
+                };
+                // the closure's term is closed once read back, so no shifting is needed
+                self.term(&closure.term, &closure.scope, 0)
+            }
+            Term::Var(index) => {
+                self.budget -= 1;
+                Term::Var(*index)
+            }
+            Term::Free(name) => {
+                self.budget -= 1;
+                Term::Free(name.clone())
+            }
+            Term::Abs(body) => {
+                self.budget -= 1;
+                Term::Abs(Box::new(self.term(body, scope, depth + 1)))
+            }
+            Term::App(callee, argument) => {
+                self.budget -= 1;
+                let callee = self.term(callee, scope, depth);
+                let argument = self.term(argument, scope, depth);
+                Term::App(Box::new(callee), Box::new(argument))
+            }
+        }
+    }
+}
@@ -1,17 +1,34 @@
-use ariadne::{Color, Fmt, Label, Report, ReportKind, Source};
-use chumsky::{Parser, Stream};
+use ariadne::{Color, Label, Report, ReportKind, Source};
+use chumsky::{prelude::end, Parser, Stream};
+use diagnostic::Diagnostic;
 use logos::Logos;
+use std::io::Write;
 
-mod lexer {
-    use logos::Logos;
+pub mod debruijn;
+pub mod decode;
+pub mod deps;
+pub mod diagnostic;
+pub mod eval;
+pub mod lint;
+pub mod prelude;
+pub mod resolve;
+
+pub mod lexer {
+    use crate::diagnostic::{Diagnostic, DiagnosticKind, Severity, Span};
+    use crate::parser::{Numerals, Options};
+    use logos::{Filter, Logos};
     use std::fmt::Formatter;
 
+    /// A token of the source, see [`tokenize`](crate::tokenize).
     #[derive(Logos, Debug, Clone, Eq, PartialEq, Hash)]
     pub enum Token<'a> {
         #[token("λ")]
+        #[token("\\")]
+        #[token("lambda")]
         Lambda,
 
         #[token(".")]
+        #[token("->")]
         Dot,
 
         #[token(":=")]
@@ -27,8 +44,25 @@ mod lexer {
         #[regex("[A-Z]+[0-9]*")]
         Ident(&'a str),
 
+        /// An integer literal, desugared into a numeral by the parser. Literals that don't fit
+        /// into a `usize` become `usize::MAX`, which [`check`] rejects as too large.
+        #[regex("[0-9]+", |lex| lex.slice().parse().unwrap_or(usize::MAX))]
+        Number(usize),
+
+        /// Ends a statement. Blank lines are part of it, indented lines continue the statement.
+        #[regex(r"(\r?\n[ \t]*)*\r?\n")]
+        Newline,
+
+        /// A `{-` without its matching `-}`, spanning the rest of the input. Terminated block
+        /// comments are skipped, just like `--` line comments.
+        #[token("{-", block_comment)]
+        UnterminatedComment,
+
+        /// Anything that isn't a token.
         #[error]
-        #[regex(r"[ \t\r\n]+", logos::skip)]
+        #[regex(r"--[^\r\n]*", logos::skip)]
+        #[regex(r"[ \t]+", logos::skip)]
+        #[regex(r"(\r?\n[ \t]*)*\r?\n[ \t]+", logos::skip)]
         Error,
     }
 
@@ -41,156 +75,998 @@ mod lexer {
                 Token::ParenO => write!(f, "("),
                 Token::ParenC => write!(f, ")"),
                 Token::Ident(ident) => write!(f, "{}", ident),
+                Token::Number(number) => write!(f, "{}", number),
+                Token::Newline => write!(f, "newline"),
+                Token::UnterminatedComment => write!(f, "unterminated comment"),
                 Token::Error => write!(f, "[error]"),
             }
         }
     }
+
+    /// Skips a block comment after its opening `{-`, including nested ones.
+    fn block_comment<'a>(lex: &mut logos::Lexer<'a, Token<'a>>) -> Filter<()> {
+        let rest = lex.remainder().as_bytes();
+        let mut depth = 1;
+        let mut i = 0;
+        while i + 1 < rest.len() {
+            match &rest[i..i + 2] {
+                b"{-" => depth += 1,
+                b"-}" => depth -= 1,
+                _ => {
+                    i += 1;
+                    continue;
+                }
+            }
+            i += 2;
+            if depth == 0 {
+                lex.bump(i);
+                return Filter::Skip;
+            }
+        }
+
+        lex.bump(rest.len());
+        Filter::Emit(())
+    }
+
+    /// Checks the `tokens` of `input` for characters that aren't part of any token, and names
+    /// that were split into several tokens because they mix cases or letters and digits.
+    /// Returns the tokens for the parser without the invalid ones, and a diagnostic for each.
+    ///
+    /// With long names, lowercase letters and digits following each other form a single name
+    /// like `acc`, `x1` or `lambdas`, which is merged into one identifier. Otherwise `ab` stays two
+    /// names.
+    /// Numbers larger than the numerals of `options` can hold are reported and read as `0`, so
+    /// that the rest of the input is still checked.
+    pub fn check<'a>(
+        input: &'a str,
+        tokens: Vec<(Token<'a>, Span)>,
+        options: Options,
+    ) -> (Vec<(Token<'a>, Span)>, Vec<Diagnostic>) {
+        let long_names = options.long_names;
+        // with long names, `lambda` is only a keyword on its own and part of names like `lambdas`
+        let in_word = |token: &Token<'a>, span: &Span| match token {
+            Token::Ident(_) | Token::Number(_) => true,
+            Token::Lambda => long_names && &input[span.clone()] == "lambda",
+            _ => false,
+        };
+        let mut checked = Vec::new();
+        let mut diagnostics = Vec::new();
+
+        let mut tokens = tokens.into_iter().peekable();
+        while let Some((token, mut span)) = tokens.next() {
+            match token {
+                Token::Error => {
+                    while let Some((Token::Error, next)) = tokens.peek() {
+                        span.end = next.end;
+                        tokens.next();
+                    }
+                    diagnostics.push(invalid_characters(&input[span.clone()], span));
+                }
+                Token::Ident(_) | Token::Number(_) | Token::Lambda if in_word(&token, &span) => {
+                    let mut word = vec![(token, span.clone())];
+                    while let Some((next_token, next)) = tokens.peek() {
+                        if next.start != span.end || !in_word(next_token, next) {
+                            break;
+                        }
+                        span.end = next.end;
+                        word.extend(tokens.next());
+                    }
+
+                    let text = &input[span.clone()];
+                    if let [(Token::Number(number), _)] = word[..] {
+                        if number > options.numerals.max() {
+                            diagnostics.push(number_too_large(
+                                text,
+                                span.clone(),
+                                options.numerals,
+                            ));
+                            checked.push((Token::Number(0), span));
+                            continue;
+                        }
+                    }
+                    if word.len() == 1 || !long_names && word.iter().all(|(t, _)| is_variable(t)) {
+                        checked.extend(word);
+                    } else if long_names && is_long_name(text) {
+                        checked.push((Token::Ident(text), span));
+                    } else {
+                        diagnostics.push(invalid_name(text, span, long_names));
+                        checked.extend(word);
+                    }
+                }
+                Token::UnterminatedComment => diagnostics.push(Diagnostic {
+                    span: span.start..span.start + "{-".len(),
+                    severity: Severity::Error,
+                    kind: DiagnosticKind::UnterminatedComment,
+                    expected: Default::default(),
+                    found: None,
+                    message: "Unterminated block comment".to_string(),
+                    help: Some("close it with `-}`".to_string()),
+                    fix: Vec::new(),
+                }),
+                // a line with only a comment on it separates the newlines around it
+                Token::Newline if matches!(checked.last(), Some((Token::Newline, _))) => {
+                    let (_, previous): &mut (_, Span) = checked.last_mut().expect("checked above");
+                    previous.end = span.end;
+                }
+                token => checked.push((token, span)),
+            }
+        }
+
+        (checked, diagnostics)
+    }
+
+    /// Single lowercase letters, which can follow each other without spaces, as in `λab.a`.
+    fn is_variable(token: &Token<'_>) -> bool {
+        matches!(token, Token::Ident(ident) if ident.starts_with(|c: char| c.is_ascii_lowercase()))
+    }
+
+    fn is_long_name(text: &str) -> bool {
+        text.starts_with(|c: char| c.is_ascii_lowercase())
+            && text
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit())
+    }
+
+    fn invalid_characters(text: &str, span: Span) -> Diagnostic {
+        let help = match text {
+            "-" | ">" => Some("did you mean `->`?"),
+            "=" | ":" => Some("did you mean `:=`?"),
+            "[" | "]" | "{" | "}" => Some("use `(` and `)` for grouping"),
+            ";" => Some("statements are separated by line breaks"),
+            _ if text.contains('_') => Some("names can't contain `_`"),
+            _ => None,
+        };
+
+        let plural = if text.chars().count() == 1 { "" } else { "s" };
+        Diagnostic {
+            span,
+            severity: Severity::Error,
+            kind: DiagnosticKind::InvalidCharacter,
+            expected: Default::default(),
+            found: Some(text.to_string()),
+            message: format!("Invalid character{plural} `{text}`"),
+            help: help.map(ToString::to_string),
+            fix: Vec::new(),
+        }
+    }
+
+    fn number_too_large(text: &str, span: Span, numerals: Numerals) -> Diagnostic {
+        Diagnostic {
+            span,
+            severity: Severity::Error,
+            kind: DiagnosticKind::NumberTooLarge,
+            expected: Default::default(),
+            found: Some(text.to_string()),
+            message: format!("Number `{text}` is too large"),
+            help: Some(format!(
+                "{} numerals can be at most {}",
+                match numerals {
+                    Numerals::Church => "Church",
+                    Numerals::Scott => "Scott",
+                    Numerals::Binary => "binary",
+                },
+                numerals.max()
+            )),
+            fix: Vec::new(),
+        }
+    }
+
+    fn invalid_name(text: &str, span: Span, long_names: bool) -> Diagnostic {
+        let global = text.to_ascii_uppercase();
+        let is_global = global.starts_with(|c: char| c.is_ascii_uppercase())
+            && global
+                .trim_start_matches(|c: char| c.is_ascii_uppercase())
+                .chars()
+                .all(|c| c.is_ascii_digit());
+
+        let help = match text.starts_with(|c: char| c.is_ascii_uppercase()) && is_global {
+            true => format!("did you mean `{global}`?"),
+            false if long_names => "variables are lowercase letters followed by digits, globals \
+                are uppercase letters followed by digits"
+                .to_string(),
+            false => "variables are single lowercase letters, globals are uppercase letters \
+                followed by digits"
+                .to_string(),
+        };
+
+        Diagnostic {
+            span,
+            severity: Severity::Error,
+            kind: DiagnosticKind::InvalidName,
+            expected: Default::default(),
+            found: Some(text.to_string()),
+            message: format!("Invalid name `{text}`"),
+            help: Some(help),
+            fix: Vec::new(),
+        }
+    }
 }
 
-mod parser {
+pub mod parser {
+    use crate::diagnostic::Span;
     use crate::lexer::Token;
     use chumsky::prelude::*;
+    use std::fmt::Formatter;
+    use std::ops::Range;
 
-    #[derive(Debug)]
+    /// Compares structurally, so `λa.a` and `λb.b` are different. Use
+    /// [`alpha_eq`](crate::debruijn::alpha_eq) to ignore the names of bound variables.
+    ///
+    /// Every node knows the span of the source it was parsed from. Nodes created during
+    /// evaluation have no span. Spans are ignored when comparing.
+    #[derive(Debug, Clone)]
     pub enum Expr {
-        Name(String),
+        Name {
+            name: String,
+            span: Option<Span>,
+        },
         Application {
             callee: Box<Expr>,
             argument: Box<Expr>,
+            span: Option<Span>,
         },
         Abstraction {
-            params: Vec<char>,
+            params: Vec<Param>,
             body: Box<Expr>,
+            span: Option<Span>,
         },
+        /// Stands in for a part of the source that couldn't be parsed, see
+        /// [`parse_program_partial`](crate::parse_program_partial).
+        Error {
+            span: Option<Span>,
+        },
+    }
+
+    /// A parameter of an abstraction. Compares by name only.
+    #[derive(Debug, Clone)]
+    pub struct Param {
+        pub name: String,
+        pub span: Option<Span>,
+    }
+
+    impl Param {
+        pub fn new(name: impl Into<String>) -> Self {
+            Param {
+                name: name.into(),
+                span: None,
+            }
+        }
+    }
+
+    impl PartialEq for Param {
+        fn eq(&self, other: &Self) -> bool {
+            self.name == other.name
+        }
+    }
+
+    impl Eq for Param {}
+
+    impl PartialEq for Expr {
+        fn eq(&self, other: &Self) -> bool {
+            match (self, other) {
+                (Expr::Name { name: a, .. }, Expr::Name { name: b, .. }) => a == b,
+                (
+                    Expr::Application {
+                        callee: a_callee,
+                        argument: a_argument,
+                        ..
+                    },
+                    Expr::Application {
+                        callee: b_callee,
+                        argument: b_argument,
+                        ..
+                    },
+                ) => a_callee == b_callee && a_argument == b_argument,
+                (
+                    Expr::Abstraction {
+                        params: a_params,
+                        body: a_body,
+                        ..
+                    },
+                    Expr::Abstraction {
+                        params: b_params,
+                        body: b_body,
+                        ..
+                    },
+                ) => a_params == b_params && a_body == b_body,
+                (Expr::Error { .. }, Expr::Error { .. }) => true,
+                _ => false,
+            }
+        }
+    }
+
+    impl Eq for Expr {}
+
+    /// One step from an expression into one of its subexpressions.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub enum Direction {
+        Callee,
+        Argument,
+        /// The body of an abstraction, after all of its parameters.
+        Body,
+    }
+
+    impl Expr {
+        /// A name without a span.
+        pub fn name(name: impl Into<String>) -> Self {
+            Expr::Name {
+                name: name.into(),
+                span: None,
+            }
+        }
+
+        /// An application without a span.
+        pub fn application(callee: Expr, argument: Expr) -> Self {
+            Expr::Application {
+                callee: Box::new(callee),
+                argument: Box::new(argument),
+                span: None,
+            }
+        }
+
+        /// An abstraction without spans.
+        pub fn abstraction(params: Vec<Param>, body: Expr) -> Self {
+            Expr::Abstraction {
+                params,
+                body: Box::new(body),
+                span: None,
+            }
+        }
+
+        /// The span of the source this expression was parsed from.
+        pub fn span(&self) -> Option<Span> {
+            match self {
+                Expr::Name { span, .. }
+                | Expr::Application { span, .. }
+                | Expr::Abstraction { span, .. }
+                | Expr::Error { span } => span.clone(),
+            }
+        }
+
+        /// Removes the spans of this expression and all of its subexpressions and parameters.
+        pub fn clear_spans(&mut self) {
+            match self {
+                Expr::Name { span, .. } | Expr::Error { span } => *span = None,
+                Expr::Application {
+                    callee,
+                    argument,
+                    span,
+                } => {
+                    callee.clear_spans();
+                    argument.clear_spans();
+                    *span = None;
+                }
+                Expr::Abstraction { params, body, span } => {
+                    params.iter_mut().for_each(|param| param.span = None);
+                    body.clear_spans();
+                    *span = None;
+                }
+            }
+        }
+
+        fn with_span(mut self, new: Span) -> Self {
+            match &mut self {
+                Expr::Name { span, .. }
+                | Expr::Application { span, .. }
+                | Expr::Abstraction { span, .. }
+                | Expr::Error { span } => *span = Some(new),
+            }
+            self
+        }
+
+        /// Follows `path` from this expression, returning `None` if it leads nowhere.
+        pub fn get(&self, path: &[Direction]) -> Option<&Expr> {
+            path.iter()
+                .try_fold(self, |expr, direction| match (direction, expr) {
+                    (Direction::Callee, Expr::Application { callee, .. }) => Some(&**callee),
+                    (Direction::Argument, Expr::Application { argument, .. }) => Some(&**argument),
+                    (Direction::Body, Expr::Abstraction { body, .. }) => Some(&**body),
+                    _ => None,
+                })
+        }
+
+        pub fn get_mut(&mut self, path: &[Direction]) -> Option<&mut Expr> {
+            path.iter()
+                .try_fold(self, |expr, direction| match (direction, expr) {
+                    (Direction::Callee, Expr::Application { callee, .. }) => Some(&mut **callee),
+                    (Direction::Argument, Expr::Application { argument, .. }) => {
+                        Some(&mut **argument)
+                    }
+                    (Direction::Body, Expr::Abstraction { body, .. }) => Some(&mut **body),
+                    _ => None,
+                })
+        }
+
+        /// Counts the names, applications and abstractions making up the expression.
+        pub fn size(&self) -> usize {
+            match self {
+                Expr::Name { .. } | Expr::Error { .. } => 1,
+                Expr::Application {
+                    callee, argument, ..
+                } => 1 + callee.size() + argument.size(),
+                Expr::Abstraction { body, .. } => 1 + body.size(),
+            }
+        }
+
+        /// Renders the expression like [`display`](Expr::display) and returns the range of
+        /// characters that the subexpression at `path` occupies.
+        pub fn render_at(
+            &self,
+            path: &[Direction],
+            style: Style,
+        ) -> (String, Option<Range<usize>>) {
+            let mut printer = Printer {
+                style,
+                target: Some(path),
+                ..Printer::default()
+            };
+            printer.write(self, false, true);
+            (printer.out, printer.highlight)
+        }
+
+        /// Prints the expression in the given `style`, see [`Display`](std::fmt::Display).
+        pub fn display(&self, style: Style) -> impl std::fmt::Display + '_ {
+            Styled { expr: self, style }
+        }
+    }
+
+    /// How expressions are printed.
+    #[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+    pub struct Style {
+        pub lambda: Lambda,
+        /// Separates parameters with spaces, as in `λa b.a`, so that the output parses back with
+        /// long names, where `λab.a` has a single parameter.
+        pub long_names: bool,
+    }
+
+    /// How abstractions are introduced.
+    #[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+    pub enum Lambda {
+        /// `λx.x`
+        #[default]
+        Unicode,
+        /// `\x.x`, for terminals and keyboards without `λ`.
+        Ascii,
+    }
+
+    /// Prints the expression in `λ` syntax with as few parentheses as possible. Nested
+    /// abstractions are merged into `λab.x` and application is left-associative.
+    impl std::fmt::Display for Expr {
+        fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+            write!(f, "{}", self.display(Style::default()))
+        }
+    }
+
+    struct Styled<'e> {
+        expr: &'e Expr,
+        style: Style,
     }
 
-    pub fn expr_parser<'a>() -> impl Parser<Token<'a>, Expr, Error = Simple<Token<'a>>> + Clone {
-        recursive(|expr| {
+    impl std::fmt::Display for Styled<'_> {
+        fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+            let mut printer = Printer {
+                style: self.style,
+                ..Printer::default()
+            };
+            printer.write(self.expr, false, true);
+            write!(f, "{}", printer.out)
+        }
+    }
+
+    /// How [`Expr::Error`] is printed.
+    pub(crate) const ERROR: &str = "<error>";
+
+    #[derive(Default)]
+    struct Printer<'p> {
+        style: Style,
+        out: String,
+        path: Vec<Direction>,
+        target: Option<&'p [Direction]>,
+        highlight: Option<Range<usize>>,
+    }
+
+    impl Printer<'_> {
+        /// `argument` is set if the expression is applied to something, `tail` if nothing follows
+        /// it before the enclosing parenthesis or the end, which lets an abstraction extend there.
+        fn write(&mut self, expr: &Expr, argument: bool, tail: bool) {
+            let parens = match expr {
+                Expr::Name { .. } | Expr::Error { .. } => false,
+                Expr::Application { .. } => argument,
+                Expr::Abstraction { .. } => !tail,
+            };
+
+            if parens {
+                self.out.push('(');
+            }
+
+            let start = (self.target == Some(&self.path[..])).then(|| self.out.chars().count());
+
+            match expr {
+                Expr::Name { name, .. } => self.out.push_str(name),
+                Expr::Error { .. } => self.out.push_str(ERROR),
+                Expr::Application {
+                    callee, argument, ..
+                } => {
+                    self.path.push(Direction::Callee);
+                    self.write(callee, false, false);
+                    self.path.pop();
+                    self.out.push(' ');
+                    self.path.push(Direction::Argument);
+                    self.write(argument, true, tail || parens);
+                    self.path.pop();
+                }
+                Expr::Abstraction { params, body, .. } => {
+                    let depth = self.path.len();
+                    self.out.push(match self.style.lambda {
+                        Lambda::Unicode => 'λ',
+                        Lambda::Ascii => '\\',
+                    });
+                    let mut all_params = params.iter().map(|param| &param.name).collect::<Vec<_>>();
+                    self.path.push(Direction::Body);
+
+                    let mut body = body;
+                    while let Expr::Abstraction {
+                        params,
+                        body: inner,
+                        ..
+                    } = &**body
+                    {
+                        all_params.extend(params.iter().map(|param| &param.name));
+                        self.path.push(Direction::Body);
+                        body = inner;
+                    }
+
+                    // single letters are written together, longer names need spaces between them
+                    let single = all_params.iter().all(|param| param.chars().count() == 1);
+                    let separator = match single && !self.style.long_names {
+                        true => "",
+                        false => " ",
+                    };
+                    for (i, param) in all_params.into_iter().enumerate() {
+                        if i > 0 {
+                            self.out.push_str(separator);
+                        }
+                        self.out.push_str(param);
+                    }
+
+                    self.out.push('.');
+                    self.write(body, false, true);
+                    self.path.truncate(depth);
+                }
+            }
+
+            if let Some(start) = start {
+                self.highlight = Some(start..self.out.chars().count());
+            }
+
+            if parens {
+                self.out.push(')');
+            }
+        }
+    }
+
+    /// Settings that change how the source is read.
+    #[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+    pub struct Options {
+        /// What integer literals desugar into.
+        pub numerals: Numerals,
+        /// Lets variables be words like `acc` or `x1`. Parameters then have to be separated by
+        /// spaces, as in `λf acc.` or `λ(f acc).`, and `λab.` has a single parameter `ab`.
+        pub long_names: bool,
+    }
+
+    /// An encoding of natural numbers as lambda terms.
+    #[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+    pub enum Numerals {
+        /// `n` applications of `f`: `λfx.f (f … x)`.
+        #[default]
+        Church,
+        /// Zero is `λzs.z`, the successor of `n` is `λzs.s n`.
+        Scott,
+        /// The bits least significant first, built from `λzot.z` for no more bits, and
+        /// `λzot.o rest` and `λzot.t rest` for a zero and a one bit followed by `rest`. Zero has
+        /// no bits.
+        Binary,
+    }
+
+    impl Numerals {
+        /// The largest number that literals can stand for. Church and Scott numerals grow with
+        /// the number, and terms that deep would overflow the stack.
+        pub fn max(self) -> usize {
+            match self {
+                Numerals::Church | Numerals::Scott => 10_000,
+                Numerals::Binary => usize::MAX - 1,
+            }
+        }
+
+        pub fn encode(self, number: usize) -> Expr {
+            let name = Expr::name;
+            let app = Expr::application;
+            let abs = |params: &str, body| {
+                Expr::abstraction(params.chars().map(Param::new).collect(), body)
+            };
+
+            match self {
+                Numerals::Church => abs(
+                    "fx",
+                    (0..number).fold(name("x"), |body, _| app(name("f"), body)),
+                ),
+                Numerals::Scott => (0..number).fold(abs("zs", name("z")), |predecessor, _| {
+                    abs("zs", app(name("s"), predecessor))
+                }),
+                Numerals::Binary => {
+                    let bits = (0..usize::BITS - number.leading_zeros()).rev();
+                    bits.fold(abs("zot", name("z")), |rest, bit| {
+                        let constructor = if number >> bit & 1 == 1 { "t" } else { "o" };
+                        abs("zot", app(name(constructor), rest))
+                    })
+                }
+            }
+        }
+    }
+
+    impl std::str::FromStr for Numerals {
+        type Err = String;
+
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            match s {
+                "church" => Ok(Numerals::Church),
+                "scott" => Ok(Numerals::Scott),
+                "binary" => Ok(Numerals::Binary),
+                _ => Err(format!(
+                    "unknown numeral encoding `{s}`, expected church, scott or binary"
+                )),
+            }
+        }
+    }
+
+    /// Puts `description`, like "name", into the expected set of errors from `filter_map`, which
+    /// is empty otherwise, also at the end of input. The set holds tokens and is only ever
+    /// printed, so the description is wrapped in an identifier.
+    fn expecting<'a>(
+        description: &'static str,
+    ) -> impl Fn(Simple<Token<'a>>) -> Simple<Token<'a>> + Copy {
+        move |error| {
+            let found = error.found().cloned();
+            Simple::expected_input_found(error.span(), [Some(Token::Ident(description))], found)
+        }
+    }
+
+    // chumsky dictates the error type returned from `filter_map`
+    #[allow(clippy::result_large_err)]
+    pub fn expr_parser<'a>(
+        options: Options,
+    ) -> impl Parser<Token<'a>, Expr, Error = Simple<Token<'a>>> + Clone {
+        recursive(move |expr| {
             let ident = filter_map(|span, token| match token {
                 Token::Ident(ident) => Ok(ident.to_string()),
                 _ => Err(Simple::expected_input_found(span, [], Some(token))),
             })
+            .map_err(expecting("name"))
             .labelled("ident");
 
             let parameters = ident
-                .map(|ident| ident.chars().collect::<Vec<_>>())
+                .map_with_span(|name, span| Param {
+                    name,
+                    span: Some(span),
+                })
+                .repeated()
+                .at_least(1);
+            let parameters = parameters
+                .delimited_by(Token::ParenO, Token::ParenC)
+                .or(parameters)
                 .labelled("parameters");
 
+            // a missing `.` is reported once the body parsed, without it the abstraction fails
+            // anyway and reporting it too would be noise
             let abstraction = just(Token::Lambda)
-                .ignore_then(parameters)
-                .then_ignore(just(Token::Dot))
+                .ignore_then(parameters.map_with_span(|params, span| (params, span)))
+                .then(just(Token::Dot).or_not())
                 .then(expr.clone())
-                .map(|(params, body)| Expr::Abstraction {
+                .validate(|(((params, params_span), dot), body), _, emit| {
+                    if dot.is_none() {
+                        emit(Simple::custom(
+                            params_span.clone(),
+                            "Expected `.` after the parameters",
+                        ));
+                    }
+                    (((params, params_span), dot), body)
+                })
+                .map_with_span(|(((params, _), _), body), span| Expr::Abstraction {
                     params,
                     body: Box::new(body),
+                    span: Some(span),
                 })
                 .labelled("abstraction");
 
             let name_expr = ident
-                .map(|ident| Expr::Name(ident.to_string()))
+                .map_with_span(|name, span| Expr::Name {
+                    name,
+                    span: Some(span),
+                })
                 .labelled("name");
 
-            let application = expr
+            // only the numeral as a whole corresponds to the source
+            let number = filter_map(move |span: Span, token| match token {
+                Token::Number(number) => Ok(options.numerals.encode(number).with_span(span)),
+                _ => Err(Simple::expected_input_found(span, [], Some(token))),
+            })
+            .map_err(expecting("number"))
+            .labelled("number");
+
+            let parenthesized = expr
                 .clone()
-                .then(expr.clone())
-                .map(|(callee, arg)| Expr::Application {
-                    callee: Box::new(callee),
-                    argument: Box::new(arg),
+                .delimited_by(Token::ParenO, Token::ParenC)
+                .recover_with(nested_delimiters(
+                    Token::ParenO,
+                    Token::ParenC,
+                    [],
+                    |span| Expr::Error { span: Some(span) },
+                ));
+
+            let atom = name_expr.or(number).or(parenthesized).labelled("atom");
+
+            // abstractions extend as far right as possible, so one can only be the last argument
+            // applications span the parentheses around their atoms, unlike the atoms themselves
+            let application = atom
+                .map_with_span(|atom, span| (atom, span))
+                .repeated()
+                .at_least(1)
+                .then(
+                    abstraction
+                        .clone()
+                        .map_with_span(|last, span| (last, span))
+                        .or_not(),
+                )
+                .map(|(atoms, last)| {
+                    atoms
+                        .into_iter()
+                        .chain(last)
+                        .reduce(|(callee, callee_span), (argument, argument_span)| {
+                            let span = callee_span.start..argument_span.end;
+                            let application = Expr::Application {
+                                callee: Box::new(callee),
+                                argument: Box::new(argument),
+                                span: Some(span.clone()),
+                            };
+                            (application, span)
+                        })
+                        .map(|(expr, _)| expr)
+                        .expect("application without atoms")
                 })
                 .labelled("application");
 
-            abstraction
-                .or(expr.clone().delimited_by(Token::ParenO, Token::ParenC))
-                .or(name_expr)
-                .or(expr)
-                .or(application)
-                .then_ignore(end())
-                .labelled("expression")
+            abstraction.or(application).labelled("expression")
+        })
+    }
+
+    /// A line of a program. Compares ignoring spans, like [`Expr`].
+    #[derive(Debug, Clone)]
+    pub enum Statement {
+        /// `NAME := expr`, binding a global that later statements can refer to. `span` is the
+        /// span of the name.
+        Definition {
+            name: String,
+            expr: Expr,
+            span: Option<Span>,
+        },
+        Expr(Expr),
+    }
+
+    impl PartialEq for Statement {
+        fn eq(&self, other: &Self) -> bool {
+            match (self, other) {
+                (
+                    Statement::Definition {
+                        name: a_name,
+                        expr: a_expr,
+                        ..
+                    },
+                    Statement::Definition {
+                        name: b_name,
+                        expr: b_expr,
+                        ..
+                    },
+                ) => a_name == b_name && a_expr == b_expr,
+                (Statement::Expr(a), Statement::Expr(b)) => a == b,
+                _ => false,
+            }
+        }
+    }
+
+    impl Eq for Statement {}
+
+    /// Parses a single statement, the tokens of one line of a program without the line break.
+    #[allow(clippy::result_large_err)]
+    pub fn statement_parser<'a>(
+        options: Options,
+    ) -> impl Parser<Token<'a>, Statement, Error = Simple<Token<'a>>> + Clone {
+        let global = filter_map(|span, token| match token {
+            Token::Ident(ident) if ident.starts_with(|c: char| c.is_ascii_uppercase()) => {
+                Ok(ident.to_string())
+            }
+            _ => Err(Simple::expected_input_found(span, [], Some(token))),
         })
+        .map_err(expecting("global name"))
+        .labelled("global name");
+
+        // what's left of a statement that can't be parsed is skipped
+        let error = |span| Expr::Error { span: Some(span) };
+
+        // a name followed by `:=` can't start an expression, so this decides the kind of statement
+        let definition = global
+            .map_with_span(|name, span| (name, span))
+            .then_ignore(just(Token::Binding))
+            .labelled("definition");
+
+        definition
+            .or_not()
+            .then(expr_parser(options).recover_with(skip_until([], error)))
+            .map(|(name, expr)| match name {
+                Some((name, span)) => Statement::Definition {
+                    name,
+                    expr,
+                    span: Some(span),
+                },
+                None => Statement::Expr(expr),
+            })
+            .then_ignore(end())
+            .recover_with(skip_until([], move |span| Statement::Expr(error(span))))
+            .labelled("statement")
     }
 }
 
-pub fn run(input: &str) {
-    let lexer = lexer::Token::lexer(input);
-    let length = lexer.source().len();
-
-    match parser::expr_parser().parse(Stream::from_iter(
-        length..length + 1,
-        lexer.spanned().inspect(|val| {
-            dbg!(val);
-        }),
-    )) {
-        Ok(ast) => println!("parsed: {ast:#?}"),
-        Err(errs) => errs
-            .into_iter()
-            .map(|e| e.map(|c| c.to_string()))
-            .for_each(|e| {
-                let report = Report::build(ReportKind::Error, (), e.span().start);
-
-                let report = match e.reason() {
-                    chumsky::error::SimpleReason::Unclosed { span, delimiter } => report
-                        .with_message(format!(
-                            "Unclosed delimiter {}",
-                            delimiter.fg(Color::Yellow)
-                        ))
-                        .with_label(
-                            Label::new(span.clone())
-                                .with_message(format!(
-                                    "Unclosed delimiter {}",
-                                    delimiter.fg(Color::Yellow)
-                                ))
-                                .with_color(Color::Yellow),
-                        )
-                        .with_label(
-                            Label::new(e.span())
-                                .with_message(format!(
-                                    "Must be closed before this {}",
-                                    e.found()
-                                        .unwrap_or(&"end of file".to_string())
-                                        .fg(Color::Red)
-                                ))
-                                .with_color(Color::Red),
-                        ),
-                    chumsky::error::SimpleReason::Unexpected => report
-                        .with_message(format!(
-                            "{}, expected {}",
-                            if e.found().is_some() {
-                                "Unexpected token in input"
-                            } else {
-                                "Unexpected end of input"
-                            },
-                            if e.expected().len() == 0 {
-                                "something else".to_string()
-                            } else {
-                                e.expected()
-                                    .map(|expected| match expected {
-                                        Some(expected) => expected.to_string(),
-                                        None => "end of input".to_string(),
-                                    })
-                                    .collect::<Vec<_>>()
-                                    .join(", ")
-                            }
-                        ))
-                        .with_label(
-                            Label::new(e.span())
-                                .with_message(format!(
-                                    "Unexpected token {}",
-                                    e.found()
-                                        .unwrap_or(&"end of file".to_string())
-                                        .fg(Color::Red)
-                                ))
-                                .with_color(Color::Red),
-                        ),
-                    chumsky::error::SimpleReason::Custom(msg) => {
-                        report.with_message(msg).with_label(
-                            Label::new(e.span())
-                                .with_message(format!("{}", msg.fg(Color::Red)))
-                                .with_color(Color::Red),
-                        )
-                    }
-                };
+/// Splits `input` into tokens, each with the span of bytes it was read from.
+pub fn tokenize(input: &str) -> Vec<(lexer::Token<'_>, diagnostic::Span)> {
+    lexer::Token::lexer(input).spanned().collect()
+}
+
+/// Parses `input` as a sequence of definitions and expressions, reporting every error in it.
+///
+/// Every line is parsed on its own, so an error never affects the statements around it. Parts
+/// that couldn't be parsed are replaced by [`Expr::Error`](parser::Expr::Error) nodes: the rest
+/// of a statement, the contents of parentheses, or a definition's body. A missing `.` after
+/// parameters is reported and otherwise ignored. Statements beyond repair are left out.
+pub fn parse_program_partial(
+    input: &str,
+    options: parser::Options,
+) -> (Vec<parser::Statement>, Vec<Diagnostic>) {
+    let (tokens, mut diagnostics) = lexer::check(input, tokenize(input), options);
+    let mut statements = Vec::new();
+    let mut line = Vec::new();
 
-                report.finish().print(Source::from(input)).unwrap();
-            }),
+    // the end of input ends the last statement like a line break
+    let length = input.len();
+    let tokens = tokens
+        .into_iter()
+        .chain([(lexer::Token::Newline, length..length + 1)]);
+
+    for (token, span) in tokens {
+        if token != lexer::Token::Newline {
+            line.push((token, span));
+            continue;
+        }
+        if line.is_empty() {
+            continue;
+        }
+
+        let stream = Stream::from_iter(span, std::mem::take(&mut line).into_iter());
+        let (statement, errors) = parser::statement_parser(options).parse_recovery(stream);
+        statements.extend(statement);
+        diagnostics.extend(errors.into_iter().map(Diagnostic::from));
     }
+
+    // an unclosed delimiter at the end of a statement is also reported as an unexpected end
+    diagnostics.sort_by_key(|diagnostic| diagnostic.span.start);
+    diagnostics.dedup_by_key(|diagnostic| diagnostic.span.clone());
+    (statements, diagnostics)
+}
+
+/// Parses `input` as a sequence of definitions and expressions.
+pub fn parse_program(
+    input: &str,
+    options: parser::Options,
+) -> Result<Vec<parser::Statement>, Vec<Diagnostic>> {
+    match parse_program_partial(input, options) {
+        (statements, diagnostics) if diagnostics.is_empty() => Ok(statements),
+        (_, diagnostics) => Err(diagnostics),
+    }
+}
+
+/// Parses `input` as a single expression with the default [`Options`](parser::Options).
+pub fn parse(input: &str) -> Result<parser::Expr, Vec<Diagnostic>> {
+    parse_with(input, parser::Options::default())
+}
+
+/// Parses `input` as a single expression. Line breaks don't separate statements here, so they
+/// are skipped like other whitespace.
+pub fn parse_with(input: &str, options: parser::Options) -> Result<parser::Expr, Vec<Diagnostic>> {
+    let (mut tokens, mut diagnostics) = lexer::check(input, tokenize(input), options);
+    tokens.retain(|(token, _)| *token != lexer::Token::Newline);
+    let length = input.len();
+
+    let (expr, errors) = parser::expr_parser(options)
+        .then_ignore(end())
+        .parse_recovery(Stream::from_iter(length..length + 1, tokens.into_iter()));
+    diagnostics.extend(errors.into_iter().map(Diagnostic::from));
+    diagnostics.sort_by_key(|diagnostic| diagnostic.span.start);
+
+    match expr {
+        Some(expr) if diagnostics.is_empty() => Ok(expr),
+        _ => Err(diagnostics),
+    }
+}
+
+/// Parses and evaluates `input`, returning the normal form of every expression in it together
+/// with the number of steps it took.
+pub fn run(input: &str) -> Result<Vec<(parser::Expr, usize)>, Vec<Diagnostic>> {
+    let mut env = eval::Env::new();
+    let mut results = Vec::new();
+    for statement in parse_program(input, parser::Options::default())? {
+        match statement {
+            parser::Statement::Definition { name, expr, .. } => env.define(name, expr),
+            parser::Statement::Expr(expr) => results.push(eval::reduce(expr, &env)),
+        }
+    }
+    Ok(results)
+}
+
+/// Writes step number `step` of a reduction trace to `out`, highlighting the redex at `path` in
+/// `expr`.
+pub fn report_step(
+    step: usize,
+    expr: &parser::Expr,
+    path: &[parser::Direction],
+    style: parser::Style,
+    out: impl Write,
+) -> std::io::Result<()> {
+    let (rendered, span) = expr.render_at(path, style);
+    let span = span.unwrap_or(0..rendered.chars().count());
+
+    Report::build(ReportKind::Advice, "term", span.start)
+        .with_message(format!("Step {}", step))
+        .with_label(
+            Label::new(("term".to_string(), span))
+                .with_message("Contracting this redex")
+                .with_color(Color::Cyan),
+        )
+        .finish()
+        .write(("term".to_string(), Source::from(rendered)), out)
+}
+
+/// Writes an error for a reduction that hit one of its limits to `out`, showing the term it got to
+/// and a note suggesting `hint` to raise the limit.
+pub fn report_gave_up(
+    gave_up: &eval::GaveUp,
+    hint: &str,
+    style: parser::Style,
+    out: impl Write,
+) -> std::io::Result<()> {
+    const MAX_SHOWN: usize = 200;
+
+    let limit = match gave_up.limit {
+        eval::Limit::Fuel(_) => "fuel",
+        eval::Limit::Timeout(_) => "time",
+        eval::Limit::Size(_) => "space",
+    };
+
+    let mut term = gave_up.expr.display(style).to_string();
+    if let Some((cut, _)) = term.char_indices().nth(MAX_SHOWN) {
+        term.truncate(cut);
+        term.push('…');
+    }
+
+    Report::build(ReportKind::Error, "term", 0)
+        .with_message(format!("Evaluation {}", gave_up))
+        .with_label(
+            Label::new(("term".to_string(), 0..term.chars().count()))
+                .with_message(format!("Reduced to this after {} steps", gave_up.steps))
+                .with_color(Color::Red),
+        )
+        .with_note(format!(
+            "the term might not have a normal form, or it needs more {}: {}",
+            limit, hint
+        ))
+        .finish()
+        .write(("term".to_string(), Source::from(term)), out)
 }
@@ -0,0 +1,475 @@
+use crate::diagnostic::{Diagnostic, DiagnosticKind, Edit, Severity, Span};
+use crate::eval::free_vars;
+use crate::lexer::Token;
+use crate::parser::{Expr, Param, Statement};
+use std::collections::{BTreeSet, HashMap};
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+/// A check for code that works, but likely has a mistake or could be simpler.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Lint {
+    /// A parameter that the body never uses, like `b` in `λab.a`.
+    UnusedParameter,
+    /// The same parameter twice in one binder, like `λaa.a`.
+    DuplicateParameter,
+    /// A parameter hiding the one of an enclosing abstraction, like the inner `a` in `λa.λa.a`.
+    Shadowing,
+    /// A definition that no other statement refers to.
+    UnusedDefinition,
+    /// Parentheses that don't change how the term is read, like in `(f a) b`.
+    RedundantParentheses,
+}
+
+impl Lint {
+    pub const ALL: [Lint; 5] = [
+        Lint::UnusedParameter,
+        Lint::DuplicateParameter,
+        Lint::Shadowing,
+        Lint::UnusedDefinition,
+        Lint::RedundantParentheses,
+    ];
+
+    /// The label at the span of a warning.
+    pub fn label(self) -> &'static str {
+        match self {
+            Lint::UnusedParameter => "This parameter is never used",
+            Lint::DuplicateParameter => "This parameter is declared twice",
+            Lint::Shadowing => "This parameter shadows another one",
+            Lint::UnusedDefinition => "This definition is never used",
+            Lint::RedundantParentheses => "These parentheses are redundant",
+        }
+    }
+
+    /// The label at the related span of a warning, see [`DiagnosticKind::Lint`].
+    pub fn related_label(self) -> &'static str {
+        match self {
+            Lint::DuplicateParameter => "First declared here",
+            Lint::Shadowing => "Shadowed parameter",
+            _ => "Related to this",
+        }
+    }
+}
+
+impl Display for Lint {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Lint::UnusedParameter => "unused-parameter",
+            Lint::DuplicateParameter => "duplicate-parameter",
+            Lint::Shadowing => "shadowing",
+            Lint::UnusedDefinition => "unused-definition",
+            Lint::RedundantParentheses => "redundant-parentheses",
+        })
+    }
+}
+
+impl FromStr for Lint {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Lint::ALL
+            .into_iter()
+            .find(|lint| lint.to_string() == s)
+            .ok_or_else(|| {
+                let all = Lint::ALL.map(|lint| lint.to_string());
+                format!("unknown lint `{s}`, expected one of {}", all.join(", "))
+            })
+    }
+}
+
+/// Which lints are checked. All of them by default.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Lints {
+    enabled: BTreeSet<Lint>,
+}
+
+impl Default for Lints {
+    fn default() -> Self {
+        Self {
+            enabled: Lint::ALL.into_iter().collect(),
+        }
+    }
+}
+
+impl Lints {
+    /// No lints at all.
+    pub fn none() -> Self {
+        Self {
+            enabled: BTreeSet::new(),
+        }
+    }
+
+    pub fn enable(&mut self, lint: Lint) {
+        self.enabled.insert(lint);
+    }
+
+    pub fn disable(&mut self, lint: Lint) {
+        self.enabled.remove(&lint);
+    }
+
+    pub fn is_enabled(&self, lint: Lint) -> bool {
+        self.enabled.contains(&lint)
+    }
+}
+
+/// Runs the enabled `lints` on `statements`, the program parsed from `input`. Only nodes with
+/// spans are checked, so terms that were built rather than parsed don't cause warnings.
+///
+/// A program without expressions is taken to be a library, whose definitions are used elsewhere,
+/// so [`Lint::UnusedDefinition`] only applies to programs with expressions.
+pub fn check(input: &str, statements: &[Statement], lints: &Lints) -> Vec<Diagnostic> {
+    let mut linter = Linter {
+        input,
+        lints,
+        parens: HashMap::new(),
+        used: BTreeSet::new(),
+        diagnostics: Vec::new(),
+    };
+
+    for statement in statements {
+        let expr = match statement {
+            Statement::Definition { expr, .. } | Statement::Expr(expr) => expr,
+        };
+        linter.used = names(expr);
+        linter.lint(expr, &mut Vec::new(), false, true);
+    }
+
+    if lints.is_enabled(Lint::UnusedDefinition) {
+        linter.unused_definitions(statements);
+    }
+    if lints.is_enabled(Lint::RedundantParentheses) {
+        linter.redundant_parentheses();
+    }
+
+    linter
+        .diagnostics
+        .sort_by_key(|diagnostic| diagnostic.span.start);
+    linter.diagnostics
+}
+
+struct Linter<'l> {
+    input: &'l str,
+    lints: &'l Lints,
+    /// Whether the expression with a span needs parentheses where it is.
+    parens: HashMap<Span, bool>,
+    /// All names in the current statement, which renamed parameters must not clash with.
+    used: BTreeSet<String>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl Linter<'_> {
+    /// `argument` and `tail` tell where `expr` is, like when printing it.
+    fn lint<'e>(&mut self, expr: &'e Expr, scope: &mut Vec<&'e Param>, argument: bool, tail: bool) {
+        if let Some(span) = expr.span() {
+            let parens = match expr {
+                Expr::Name { .. } | Expr::Error { .. } => false,
+                Expr::Application { .. } => argument,
+                Expr::Abstraction { .. } => !tail,
+            };
+            self.parens.entry(span).or_insert(parens);
+        }
+
+        match expr {
+            Expr::Name { .. } | Expr::Error { .. } => {}
+            Expr::Application {
+                callee,
+                argument: arg,
+                ..
+            } => {
+                self.lint(callee, scope, false, false);
+                self.lint(arg, scope, true, tail || argument);
+            }
+            Expr::Abstraction { params, body, .. } => {
+                self.parameters(params, body, scope);
+                let len = scope.len();
+                scope.extend(params);
+                self.lint(body, scope, false, true);
+                scope.truncate(len);
+            }
+        }
+    }
+
+    /// Checks the parameters of one binder, with the parameters of the enclosing binders in
+    /// `scope`.
+    fn parameters(&mut self, params: &[Param], body: &Expr, scope: &[&Param]) {
+        let free = free_vars(body);
+
+        for (i, param) in params.iter().enumerate() {
+            let span = match &param.span {
+                Some(span) => span.clone(),
+                None => continue,
+            };
+            let name = &param.name;
+
+            // the first of two equal parameters is the one that can't be used
+            if let Some(first) = params[..i].iter().find(|other| other.name == *name) {
+                self.warn(Diagnostic {
+                    span,
+                    severity: Severity::Warning,
+                    kind: DiagnosticKind::Lint {
+                        lint: Lint::DuplicateParameter,
+                        related: first.span.clone(),
+                    },
+                    expected: Default::default(),
+                    found: Some(name.clone()),
+                    message: format!("Duplicate parameter `{name}`"),
+                    help: Some(format!("the first `{name}` can never be used")),
+                    fix: Vec::new(),
+                });
+                continue;
+            }
+            if params[i + 1..].iter().any(|other| other.name == *name) {
+                continue;
+            }
+
+            if !free.contains(name) {
+                self.warn(Diagnostic {
+                    span: span.clone(),
+                    severity: Severity::Warning,
+                    kind: DiagnosticKind::Lint {
+                        lint: Lint::UnusedParameter,
+                        related: None,
+                    },
+                    expected: Default::default(),
+                    found: Some(name.clone()),
+                    message: format!("Unused parameter `{name}`"),
+                    help: None,
+                    fix: Vec::new(),
+                });
+            }
+
+            if let Some(shadowed) = scope.iter().rev().find(|other| other.name == *name) {
+                let (help, fix) = match self.rename(param, body) {
+                    Some((fresh, fix)) => (Some(format!("rename it to `{fresh}`")), fix),
+                    None => (None, Vec::new()),
+                };
+                self.warn(Diagnostic {
+                    span,
+                    severity: Severity::Warning,
+                    kind: DiagnosticKind::Lint {
+                        lint: Lint::Shadowing,
+                        related: shadowed.span.clone(),
+                    },
+                    expected: Default::default(),
+                    found: Some(name.clone()),
+                    message: format!("Parameter `{name}` shadows an outer one"),
+                    help,
+                    fix,
+                });
+            }
+        }
+    }
+
+    /// Renames `param` and its uses in `body` to a name not used in the statement yet.
+    fn rename(&mut self, param: &Param, body: &Expr) -> Option<(String, Vec<Edit>)> {
+        fn uses(expr: &Expr, name: &str, spans: &mut Vec<Span>) -> Option<()> {
+            match expr {
+                Expr::Name { name: used, span } if used == name => spans.push(span.clone()?),
+                Expr::Name { .. } | Expr::Error { .. } => {}
+                Expr::Application {
+                    callee, argument, ..
+                } => {
+                    uses(callee, name, spans)?;
+                    uses(argument, name, spans)?;
+                }
+                Expr::Abstraction { params, .. } if params.iter().any(|p| p.name == name) => {}
+                Expr::Abstraction { body, .. } => uses(body, name, spans)?,
+            }
+            Some(())
+        }
+
+        let fresh = ('a'..='z')
+            .map(String::from)
+            .find(|fresh| !self.used.contains(fresh))?;
+        let mut spans = vec![param.span.clone()?];
+        uses(body, &param.name, &mut spans)?;
+
+        self.used.insert(fresh.clone());
+        let fix = spans
+            .into_iter()
+            .map(|span| Edit {
+                span,
+                replacement: fresh.clone(),
+            })
+            .collect();
+        Some((fresh, fix))
+    }
+
+    fn unused_definitions(&mut self, statements: &[Statement]) {
+        if !statements
+            .iter()
+            .any(|statement| matches!(statement, Statement::Expr(_)))
+        {
+            return;
+        }
+
+        for (i, statement) in statements.iter().enumerate() {
+            let (name, expr, span) = match statement {
+                Statement::Definition {
+                    name,
+                    expr,
+                    span: Some(span),
+                } => (name, expr, span),
+                _ => continue,
+            };
+
+            let used = statements.iter().enumerate().any(|(j, other)| {
+                let (Statement::Definition { expr: other, .. } | Statement::Expr(other)) = other;
+                j != i && free_vars(other).contains(name)
+            });
+            if used {
+                continue;
+            }
+
+            // removes the rest of the line the expression ends on too, like closing parentheses
+            // and comments
+            let fix = expr.span().map(|expr| {
+                let end = match self.input[expr.end..].find('\n') {
+                    Some(newline) => expr.end + newline + 1,
+                    None => self.input.len(),
+                };
+                Edit {
+                    span: span.start..end,
+                    replacement: String::new(),
+                }
+            });
+
+            self.warn(Diagnostic {
+                span: span.clone(),
+                severity: Severity::Warning,
+                kind: DiagnosticKind::Lint {
+                    lint: Lint::UnusedDefinition,
+                    related: None,
+                },
+                expected: Default::default(),
+                found: Some(name.clone()),
+                message: format!("Unused definition `{name}`"),
+                help: Some("remove it".to_string()),
+                fix: fix.into_iter().collect(),
+            });
+        }
+    }
+
+    fn redundant_parentheses(&mut self) {
+        let tokens = crate::tokenize(self.input);
+        let mut open = Vec::new();
+
+        for (i, (token, _)) in tokens.iter().enumerate() {
+            match token {
+                Token::ParenO => open.push(i),
+                Token::ParenC => {
+                    if let Some(start) = open.pop() {
+                        self.parenthesized(&tokens[start..=i]);
+                    }
+                }
+                Token::Newline => open.clear(),
+                _ => {}
+            }
+        }
+    }
+
+    /// Checks the parentheses around `tokens[1..len - 1]`.
+    fn parenthesized(&mut self, tokens: &[(Token<'_>, Span)]) {
+        let (open, close) = (&tokens[0].1, &tokens[tokens.len() - 1].1);
+        let inner = &tokens[1..tokens.len() - 1];
+        let content = match inner {
+            [] => return,
+            [(_, first), .., (_, last)] => first.start..last.end,
+            [(_, only)] => only.clone(),
+        };
+
+        // parentheses right inside others, if they enclose all of their content
+        let double = matches!(inner.first(), Some((Token::ParenO, _)))
+            && inner
+                .iter()
+                .scan(0, |depth, (token, _)| {
+                    match token {
+                        Token::ParenO => *depth += 1,
+                        Token::ParenC => *depth -= 1,
+                        _ => {}
+                    }
+                    Some(*depth)
+                })
+                .position(|depth| depth == 0)
+                == Some(inner.len() - 1);
+        // numbers are atoms, even though they desugar into abstractions
+        let number = matches!(inner, [(Token::Number(_), _)]);
+
+        if !double && !number && self.parens.get(&content) != Some(&false) {
+            return;
+        }
+
+        let fix = vec![
+            Edit {
+                span: open.clone(),
+                replacement: self.separator(open).to_string(),
+            },
+            Edit {
+                span: close.clone(),
+                replacement: self.separator(close).to_string(),
+            },
+        ];
+        self.warn(Diagnostic {
+            span: open.start..close.end,
+            severity: Severity::Warning,
+            kind: DiagnosticKind::Lint {
+                lint: Lint::RedundantParentheses,
+                related: None,
+            },
+            expected: Default::default(),
+            found: Some(self.input[open.start..close.end].to_string()),
+            message: "Redundant parentheses".to_string(),
+            help: Some("remove them".to_string()),
+            fix,
+        });
+    }
+
+    /// What a parenthesis at `span` is replaced with, a space if removing it would join the names
+    /// around it.
+    fn separator(&self, span: &Span) -> &'static str {
+        let before = self.input[..span.start].chars().next_back();
+        let after = self.input[span.end..].chars().next();
+        match (before, after) {
+            (Some(before), Some(after)) if before.is_alphanumeric() && after.is_alphanumeric() => {
+                " "
+            }
+            _ => "",
+        }
+    }
+
+    fn warn(&mut self, diagnostic: Diagnostic) {
+        let lint = match diagnostic.kind {
+            DiagnosticKind::Lint { lint, .. } => lint,
+            _ => unreachable!("only lints are reported"),
+        };
+        if self.lints.is_enabled(lint) {
+            self.diagnostics.push(diagnostic);
+        }
+    }
+}
+
+/// The names of all variables, globals and parameters in `expr`.
+fn names(expr: &Expr) -> BTreeSet<String> {
+    fn collect(expr: &Expr, names: &mut BTreeSet<String>) {
+        match expr {
+            Expr::Name { name, .. } => {
+                names.insert(name.clone());
+            }
+            Expr::Application {
+                callee, argument, ..
+            } => {
+                collect(callee, names);
+                collect(argument, names);
+            }
+            Expr::Abstraction { params, body, .. } => {
+                names.extend(params.iter().map(|param| param.name.clone()));
+                collect(body, names);
+            }
+            Expr::Error { .. } => {}
+        }
+    }
+
+    let mut names = BTreeSet::new();
+    collect(expr, &mut names);
+    names
+}
@@ -1,7 +1,362 @@
+use lambda_calculus::{
+    deps::{self, Combinator},
+    diagnostic::{self, Diagnostic, Severity},
+    eval::{Env, GaveUp, Limit, Limits, Strategy},
+    lint::{self, Lints},
+    parser::{self, Expr, Lambda, Statement, Style},
+    resolve,
+};
+use std::time::Duration;
+
+mod repl;
+
+const USAGE: &str = "\
+usage: lambda-calculus [OPTIONS] [FILE]...
+
+Evaluates the files in order, or starts a REPL if none are given.
+
+options:
+  --strategy <STRATEGY>  normal (default), applicative, call-by-name, call-by-value or
+                         call-by-need
+  --numerals <ENCODING>  what integer literals stand for: church (default), scott or binary
+  --no-prelude           start without the standard definitions like TRUE, ADD and Y
+  --long-names           allow variables like `acc`, parameters are then separated by spaces
+  --trace                print every beta step with its redex highlighted
+  --fix-point            rewrite recursive definitions with the Y combinator, or Z for
+                         call-by-value, applicative order can't evaluate them at all
+  --fuel <STEPS>         give up after this many beta steps and expansions of globals
+                         (default: unlimited)
+  --timeout <SECONDS>    give up after this much time (default: 10)
+  --max-size <NODES>     give up once the term grows beyond this size (default: 100000)
+  --ascii                print abstractions as `\\x.x` instead of `λx.x`
+  --tokens               print the tokens of the files instead of evaluating them
+  --error-format <FORMAT>  print parse errors as human (default) reports or json
+  --allow <LINT>         don't warn about LINT: unused-parameter, duplicate-parameter,
+                         shadowing, unused-definition or redundant-parentheses
+  --help                 show this message";
+
+/// Settings shared by the file runner and the REPL.
+#[derive(Debug, Clone)]
+pub struct Options {
+    pub syntax: parser::Options,
+    pub strategy: Strategy,
+    pub limits: Limits,
+    pub trace: bool,
+    pub style: Style,
+    pub prelude: bool,
+    pub error_format: ErrorFormat,
+    pub lints: Lints,
+    /// Rewrite recursive definitions with a fixed-point combinator instead of warning about them.
+    pub fix_point: bool,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            syntax: parser::Options::default(),
+            strategy: Strategy::default(),
+            limits: Limits {
+                fuel: None,
+                timeout: Some(Duration::from_secs(10)),
+                max_size: Some(100_000),
+            },
+            trace: false,
+            style: Style::default(),
+            prelude: true,
+            error_format: ErrorFormat::default(),
+            lints: Lints::default(),
+            fix_point: false,
+        }
+    }
+}
+
+impl Options {
+    /// Checks `statements`, parsed from `input`, for recursive definitions and lints. Recursive
+    /// definitions are rewritten if that is enabled, which leaves only the errors for the ones
+    /// that can't be. Under applicative order, where no rewrite helps, all of them are errors.
+    pub fn analyze(&self, input: &str, statements: &mut [Statement]) -> Vec<Diagnostic> {
+        let mut diagnostics = lint::check(input, statements, &self.lints);
+        let mut recursive = deps::check(statements);
+        if self.fix_point {
+            match Combinator::for_strategy(self.strategy) {
+                Some(combinator) => {
+                    deps::rewrite(statements, combinator);
+                    recursive.retain(Diagnostic::is_error);
+                }
+                None => {
+                    for diagnostic in recursive.iter_mut().filter(|d| !d.is_error()) {
+                        diagnostic.severity = Severity::Error;
+                        diagnostic.help = Some(
+                            "applicative order reduces under λ, so it unfolds every fixed-point \
+                             combinator forever and can't evaluate recursive definitions"
+                                .to_string(),
+                        );
+                    }
+                }
+            }
+        }
+        diagnostics.extend(recursive);
+        diagnostics
+    }
+
+    /// The environment user code starts out with.
+    pub fn env(&self) -> Env {
+        match self.prelude {
+            true => lambda_calculus::prelude::env(),
+            false => Env::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ErrorFormat {
+    #[default]
+    Human,
+    Json,
+}
+
+impl std::str::FromStr for ErrorFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "human" => Ok(ErrorFormat::Human),
+            "json" => Ok(ErrorFormat::Json),
+            _ => Err(format!(
+                "unknown error format `{s}`, expected human or json"
+            )),
+        }
+    }
+}
+
+/// Deeply nested terms recurse deeply in the evaluator and printer, more than the main thread's
+/// stack allows for.
+const STACK_SIZE: usize = 512 * 1024 * 1024;
+
 fn main() {
-    lambda_calculus::run("fa");
-    // lambda_calculus::run("λa.a");
-    // lambda_calculus::run("λa.a");
-    // lambda_calculus::run("λab.PAIR a");
-    // lambda_calculus::run("U := λab.a");
+    let cli = std::thread::Builder::new()
+        .stack_size(STACK_SIZE)
+        .spawn(run)
+        .expect("failed to spawn the evaluation thread");
+
+    if cli.join().is_err() {
+        std::process::exit(101);
+    }
+}
+
+fn run() {
+    let mut options = Options::default();
+    let mut paths = Vec::new();
+    let mut tokens = false;
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--strategy" => match args.next().as_deref().map(str::parse) {
+                Some(Ok(strategy)) => options.strategy = strategy,
+                Some(Err(err)) => usage_error(&err),
+                None => usage_error("`--strategy` needs a value"),
+            },
+            "--numerals" => match args.next().as_deref().map(str::parse) {
+                Some(Ok(numerals)) => options.syntax.numerals = numerals,
+                Some(Err(err)) => usage_error(&err),
+                None => usage_error("`--numerals` needs a value"),
+            },
+            "--no-prelude" => options.prelude = false,
+            "--long-names" => {
+                options.syntax.long_names = true;
+                options.style.long_names = true;
+            }
+            "--trace" => options.trace = true,
+            "--fix-point" => options.fix_point = true,
+            "--ascii" => options.style.lambda = Lambda::Ascii,
+            "--tokens" => tokens = true,
+            "--fuel" => options.limits.fuel = Some(value(&mut args, "--fuel")),
+            "--timeout" => {
+                let seconds = value(&mut args, "--timeout");
+                match Duration::try_from_secs_f64(seconds) {
+                    Ok(timeout) => options.limits.timeout = Some(timeout),
+                    Err(err) => usage_error(&format!("invalid value for `--timeout`: {err}")),
+                }
+            }
+            "--max-size" => options.limits.max_size = Some(value(&mut args, "--max-size")),
+            "--error-format" => match args.next().as_deref().map(str::parse) {
+                Some(Ok(format)) => options.error_format = format,
+                Some(Err(err)) => usage_error(&err),
+                None => usage_error("`--error-format` needs a value"),
+            },
+            "--allow" => match args.next().as_deref().map(str::parse) {
+                Some(Ok(lint)) => options.lints.disable(lint),
+                Some(Err(err)) => usage_error(&err),
+                None => usage_error("`--allow` needs a value"),
+            },
+            "-h" | "--help" => return println!("{USAGE}"),
+            flag if flag.starts_with("--") => usage_error(&format!("unknown option `{flag}`")),
+            _ => paths.push(arg),
+        }
+    }
+
+    if tokens {
+        if !print_tokens(&paths) {
+            std::process::exit(1);
+        }
+    } else if paths.is_empty() {
+        repl::run(options);
+    } else if !run_files(&paths, &options) {
+        std::process::exit(1);
+    }
+}
+
+/// Parses the value following the option `name`.
+fn value<T: std::str::FromStr>(args: &mut impl Iterator<Item = String>, name: &str) -> T {
+    match args.next().map(|arg| arg.parse()) {
+        Some(Ok(value)) => value,
+        Some(Err(_)) => usage_error(&format!("invalid value for `{name}`")),
+        None => usage_error(&format!("`{name}` needs a value")),
+    }
+}
+
+fn usage_error(message: &str) -> ! {
+    eprintln!("{message}\n\n{USAGE}");
+    std::process::exit(2);
+}
+
+/// Reduces `expr` with the configured strategy and limits, printing every step if tracing is
+/// enabled.
+pub fn evaluate(expr: Expr, env: &Env, options: &Options) -> Result<(Expr, usize), GaveUp> {
+    if options.trace && options.strategy == Strategy::CallByNeed {
+        eprintln!("call-by-need shares arguments outside of the term, so it can't be traced");
+    }
+
+    let mut step = 0;
+    lambda_calculus::eval::reduce_with(expr, env, options.strategy, options.limits, |expr, path| {
+        if options.trace {
+            step += 1;
+            lambda_calculus::report_step(step, expr, path, options.style, std::io::stdout())
+                .expect("failed to write to stdout");
+        }
+    })
+}
+
+/// Renders a result in the configured style, annotated with the value it encodes if it looks
+/// like a Church numeral, boolean, pair or list.
+pub fn show(expr: &Expr, options: &Options) -> String {
+    let rendered = expr.display(options.style);
+    match lambda_calculus::decode::describe(expr) {
+        Some(value) => format!("{rendered} = {value}"),
+        None => rendered.to_string(),
+    }
+}
+
+/// Reports a reduction that gave up, pointing at the command line option for its limit.
+pub fn report_gave_up(gave_up: &GaveUp, options: &Options) {
+    let option = match gave_up.limit {
+        Limit::Fuel(_) => "--fuel",
+        Limit::Timeout(_) => "--timeout",
+        Limit::Size(_) => "--max-size",
+    };
+    let hint = format!("raise it with `{option}`");
+    lambda_calculus::report_gave_up(gave_up, &hint, options.style, std::io::stderr())
+        .expect("failed to write to stderr");
+}
+
+/// Prints `diagnostics` for the source `input` named `source_id` to stderr in the configured
+/// format, if there are any.
+pub fn report_errors(source_id: &str, input: &str, diagnostics: &[Diagnostic], options: &Options) {
+    if diagnostics.is_empty() {
+        return;
+    }
+
+    match options.error_format {
+        ErrorFormat::Human => diagnostic::write(diagnostics, source_id, input, std::io::stderr())
+            .expect("failed to write to stderr"),
+        ErrorFormat::Json => eprintln!("{}", diagnostic::to_json(diagnostics, source_id)),
+    }
+}
+
+/// Prints every token of `input` with its span, one per line.
+pub fn print_token_stream(input: &str) {
+    for (token, span) in lambda_calculus::tokenize(input) {
+        println!("{:>5}..{:<5} {:?}", span.start, span.end, token);
+    }
+}
+
+/// Prints the tokens of every file. Returns `false` if one couldn't be read.
+fn print_tokens(paths: &[String]) -> bool {
+    let mut ok = true;
+    for path in paths {
+        match std::fs::read_to_string(path) {
+            Ok(source) => {
+                println!("{path}:");
+                print_token_stream(&source);
+            }
+            Err(err) => {
+                eprintln!("could not read `{path}`: {err}");
+                ok = false;
+            }
+        }
+    }
+    ok
+}
+
+/// Evaluates the files in order, sharing definitions between them, and prints the normal form of
+/// every expression. Nothing is evaluated unless all files parse and their names resolve. Returns
+/// `false` on failure.
+fn run_files(paths: &[String], options: &Options) -> bool {
+    let mut programs = Vec::new();
+    let mut ok = true;
+    // the definitions of the files so far, which later files can refer to
+    let mut defined = options.env();
+
+    for path in paths {
+        let source = match std::fs::read_to_string(path) {
+            Ok(source) => source,
+            Err(err) => {
+                eprintln!("could not read `{path}`: {err}");
+                ok = false;
+                continue;
+            }
+        };
+
+        match lambda_calculus::parse_program(&source, options.syntax) {
+            Ok(mut statements) => {
+                let mut diagnostics = resolve::check(&statements, &defined);
+                diagnostics.extend(options.analyze(&source, &mut statements));
+                diagnostics.sort_by_key(|diagnostic| diagnostic.span.start);
+                report_errors(path, &source, &diagnostics, options);
+                ok &= !diagnostics.iter().any(Diagnostic::is_error);
+
+                for statement in &statements {
+                    if let Statement::Definition { name, expr, .. } = statement {
+                        defined.define(name.clone(), expr.clone());
+                    }
+                }
+                programs.push(statements);
+            }
+            Err(errs) => {
+                report_errors(path, &source, &errs, options);
+                ok = false;
+            }
+        }
+    }
+
+    if !ok {
+        return false;
+    }
+
+    let mut env = options.env();
+    for statement in programs.into_iter().flatten() {
+        match statement {
+            Statement::Definition { name, expr, .. } => env.define(name, expr),
+            Statement::Expr(expr) => match evaluate(expr, &env, options) {
+                Ok((normal, _)) => println!("{}", show(&normal, options)),
+                Err(gave_up) => {
+                    report_gave_up(&gave_up, options);
+                    ok = false;
+                }
+            },
+        }
+    }
+
+    ok
 }
@@ -0,0 +1,21 @@
+use crate::eval::Env;
+use crate::parser::{Options, Statement};
+
+/// Definitions of the usual combinators, booleans, pairs, Church numerals and Church lists.
+///
+/// Numbers in the prelude are Church numerals regardless of the encoding chosen for user code,
+/// since the arithmetic here only works on those.
+pub const SOURCE: &str = include_str!("prelude.lc");
+
+/// An environment containing all definitions of the prelude.
+pub fn env() -> Env {
+    let statements = crate::parse_program(SOURCE, Options::default()).expect("the prelude parses");
+
+    let mut env = Env::new();
+    for statement in statements {
+        if let Statement::Definition { name, expr, .. } = statement {
+            env.define(name, expr);
+        }
+    }
+    env
+}
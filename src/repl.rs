@@ -0,0 +1,115 @@
+use crate::Options;
+use lambda_calculus::{diagnostic::Diagnostic, eval::Env, parser::Statement, resolve};
+use rustyline::{error::ReadlineError, Editor};
+
+const HELP: &str = "\
+:load <file>  evaluate a file and keep its definitions
+:env          list all definitions
+:steps        toggle printing the number of beta steps
+:strategy <s> switch to evaluation strategy s, or show the current one
+:trace        toggle printing every beta step
+:tokens <e>   show the tokens of e
+:help         show this message
+:quit         exit the REPL";
+
+struct Repl {
+    env: Env,
+    options: Options,
+    show_steps: bool,
+}
+
+pub fn run(options: Options) {
+    let mut editor = Editor::<()>::new();
+    let mut repl = Repl {
+        env: options.env(),
+        options,
+        show_steps: false,
+    };
+
+    loop {
+        match editor.readline("λ> ") {
+            Ok(line) => {
+                editor.add_history_entry(line.as_str());
+                if !repl.handle(line.trim()) {
+                    break;
+                }
+            }
+            Err(ReadlineError::Interrupted) => continue,
+            Err(ReadlineError::Eof) => break,
+            Err(err) => {
+                eprintln!("error: {err}");
+                break;
+            }
+        }
+    }
+}
+
+impl Repl {
+    /// Handles a single line of input. Returns `false` once the user asked to quit.
+    fn handle(&mut self, line: &str) -> bool {
+        match line.split_once(char::is_whitespace).unwrap_or((line, "")) {
+            (":quit" | ":q", _) => return false,
+            (":help", _) => println!("{HELP}"),
+            (":env", _) => {
+                for (name, expr) in self.env.iter() {
+                    println!("{name} := {}", expr.display(self.options.style));
+                }
+            }
+            (":steps", _) => {
+                self.show_steps = !self.show_steps;
+                println!("step counts {}", if self.show_steps { "on" } else { "off" });
+            }
+            (":trace", _) => {
+                self.options.trace = !self.options.trace;
+                println!("tracing {}", if self.options.trace { "on" } else { "off" });
+            }
+            (":strategy", "") => println!("{:?}", self.options.strategy),
+            (":strategy", strategy) => match strategy.trim().parse() {
+                Ok(strategy) => self.options.strategy = strategy,
+                Err(err) => eprintln!("{err}"),
+            },
+            (":tokens", input) => crate::print_token_stream(input),
+            (":load", path) => self.load(path.trim()),
+            (command, _) if command.starts_with(':') => {
+                eprintln!("unknown command `{command}`, see :help")
+            }
+            _ => self.eval("<repl>", line),
+        }
+        true
+    }
+
+    fn load(&mut self, path: &str) {
+        match std::fs::read_to_string(path) {
+            Ok(source) => self.eval(path, &source),
+            Err(err) => eprintln!("could not read `{path}`: {err}"),
+        }
+    }
+
+    fn eval(&mut self, source_id: &str, input: &str) {
+        let mut statements = match lambda_calculus::parse_program(input, self.options.syntax) {
+            Ok(statements) => statements,
+            Err(errs) => return crate::report_errors(source_id, input, &errs, &self.options),
+        };
+
+        let mut diagnostics = resolve::check(&statements, &self.env);
+        diagnostics.extend(self.options.analyze(input, &mut statements));
+        diagnostics.sort_by_key(|diagnostic| diagnostic.span.start);
+        crate::report_errors(source_id, input, &diagnostics, &self.options);
+        if diagnostics.iter().any(Diagnostic::is_error) {
+            return;
+        }
+
+        for statement in statements {
+            match statement {
+                Statement::Definition { name, expr, .. } => self.env.define(name, expr),
+                Statement::Expr(expr) => match crate::evaluate(expr, &self.env, &self.options) {
+                    Ok((normal, steps)) if self.show_steps => {
+                        println!("{} ({steps} steps)", crate::show(&normal, &self.options))
+                    }
+                    Ok((normal, _)) => println!("{}", crate::show(&normal, &self.options)),
+                    Err(gave_up) => crate::report_gave_up(&gave_up, &self.options),
+                },
+            }
+        }
+    }
+}
@@ -0,0 +1,163 @@
+use crate::diagnostic::{Diagnostic, DiagnosticKind, Severity};
+use crate::eval::Env;
+use crate::parser::{Expr, Statement};
+use std::collections::BTreeSet;
+
+/// Checks that every name in `statements`, a program run in `env`, refers to something. Globals
+/// that aren't defined are errors, variables that aren't bound by an abstraction are warnings.
+///
+/// Definitions are expanded lazily, so their bodies may refer to globals defined anywhere in the
+/// program, or even ones defined later on, like the second of two mutually recursive definitions
+/// entered one at a time. Undefined globals in them are only warnings. Expressions are evaluated
+/// right away and only see the globals defined before them. Names are suggested from the
+/// definitions and the variables in scope.
+pub fn check(statements: &[Statement], env: &Env) -> Vec<Diagnostic> {
+    let program = statements
+        .iter()
+        .filter_map(|statement| match statement {
+            Statement::Definition { name, .. } => Some(name.as_str()),
+            Statement::Expr(_) => None,
+        })
+        .collect::<Vec<_>>();
+
+    let mut defined = env.iter().map(|(name, _)| name).collect::<BTreeSet<_>>();
+    let mut diagnostics = Vec::new();
+
+    for statement in statements {
+        let (expr, visible, definition) = match statement {
+            Statement::Definition { name, expr, .. } => (
+                expr,
+                defined.iter().chain(&program).copied().collect(),
+                Some(name.as_str()),
+            ),
+            Statement::Expr(expr) => (expr, defined.clone(), None),
+        };
+
+        Resolver {
+            defined: &visible,
+            definition,
+            bound: Vec::new(),
+            diagnostics: &mut diagnostics,
+        }
+        .resolve(expr);
+
+        if let Statement::Definition { name, .. } = statement {
+            defined.insert(name);
+        }
+    }
+
+    diagnostics
+}
+
+struct Resolver<'r> {
+    defined: &'r BTreeSet<&'r str>,
+    /// The name of the definition being resolved, if it is one.
+    definition: Option<&'r str>,
+    bound: Vec<&'r str>,
+    diagnostics: &'r mut Vec<Diagnostic>,
+}
+
+impl<'r> Resolver<'r> {
+    fn resolve(&mut self, expr: &'r Expr) {
+        match expr {
+            Expr::Name { name, span } => {
+                let span = match span {
+                    Some(span) => span.clone(),
+                    // only names from the source can be pointed at
+                    None => return,
+                };
+                let global = name.starts_with(|c: char| c.is_ascii_uppercase());
+                if global && !self.defined.contains(name.as_str()) {
+                    let suggestion = self.suggest(name, self.defined.iter());
+                    let (severity, help) = match self.definition {
+                        Some(definition) => (
+                            Severity::Warning,
+                            suggestion.or_else(|| {
+                                Some(format!(
+                                    "define it before evaluating anything that uses `{definition}`"
+                                ))
+                            }),
+                        ),
+                        None => (Severity::Error, suggestion),
+                    };
+                    self.diagnostics.push(Diagnostic {
+                        span,
+                        severity,
+                        kind: DiagnosticKind::UndefinedName,
+                        expected: Default::default(),
+                        found: Some(name.clone()),
+                        message: format!("Undefined name `{name}`"),
+                        help,
+                        fix: Vec::new(),
+                    });
+                } else if !global && !self.bound.contains(&name.as_str()) {
+                    self.diagnostics.push(Diagnostic {
+                        span,
+                        severity: Severity::Warning,
+                        kind: DiagnosticKind::FreeVariable,
+                        expected: Default::default(),
+                        found: Some(name.clone()),
+                        message: format!("Free variable `{name}`"),
+                        help: self.suggest(name, self.bound.iter().rev()),
+                        fix: Vec::new(),
+                    });
+                }
+            }
+            Expr::Application {
+                callee, argument, ..
+            } => {
+                self.resolve(callee);
+                self.resolve(argument);
+            }
+            Expr::Abstraction { params, body, .. } => {
+                let len = self.bound.len();
+                self.bound
+                    .extend(params.iter().map(|param| param.name.as_str()));
+                self.resolve(body);
+                self.bound.truncate(len);
+            }
+            Expr::Error { .. } => {}
+        }
+    }
+
+    /// Suggests the one of `candidates` closest to `name`, if it is close enough to be a typo. A
+    /// long variable spelling a definition in lowercase, like `pair`, suggests the definition
+    /// instead.
+    fn suggest<'c>(
+        &self,
+        name: &str,
+        candidates: impl Iterator<Item = &'c &'c str>,
+    ) -> Option<String> {
+        // `y` is rarely meant to be `Y`
+        let uppercase = name.to_ascii_uppercase();
+        if name.len() > 1 && name != uppercase && self.defined.contains(uppercase.as_str()) {
+            return Some(format!("did you mean `{uppercase}`?"));
+        }
+
+        // replacing every character of a name isn't a typo
+        let max_distance = (name.chars().count() / 3).max(1);
+        candidates
+            .map(|candidate| (distance(name, candidate), candidate))
+            .filter(|&(distance, _)| distance <= max_distance && distance < name.chars().count())
+            .min_by_key(|&(distance, _)| distance)
+            .map(|(_, candidate)| format!("did you mean `{candidate}`?"))
+    }
+}
+
+/// The Levenshtein distance between `a` and `b`, the number of characters to insert, delete or
+/// replace to turn one into the other.
+fn distance(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<_>>();
+    let mut previous = (0..=b.len()).collect::<Vec<_>>();
+
+    for (i, a) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, b) in b.iter().enumerate() {
+            let replace = previous[j] + usize::from(a != *b);
+            current.push(replace.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+
+    previous[b.len()]
+}
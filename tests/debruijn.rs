@@ -0,0 +1,59 @@
+use lambda_calculus::debruijn::{alpha_eq, Term};
+use lambda_calculus::parser::{Expr, Options};
+
+fn parse(input: &str) -> Expr {
+    lambda_calculus::parse(input).unwrap()
+}
+
+#[test]
+fn converts_to_and_from_terms() {
+    let expr = parse("λab.a (λc.b c) F");
+    let term = Term::from(&expr);
+    let var = |index| Box::new(Term::Var(index));
+    assert_eq!(
+        term,
+        Term::Abs(Box::new(Term::Abs(Box::new(Term::App(
+            Box::new(Term::App(
+                var(1),
+                Box::new(Term::Abs(Box::new(Term::App(var(1), var(0))))),
+            )),
+            Box::new(Term::Free("F".to_string())),
+        )))))
+    );
+
+    let back = Expr::from(&term);
+    assert!(alpha_eq(&back, &expr), "converted back to {back}");
+    assert_eq!(Term::from(&back), term);
+}
+
+#[test]
+fn fresh_names_parse_back() {
+    // more binders than there are letters, every one of them used in the body
+    let body = (1..30).fold(Term::Var(0), |body, index| {
+        Term::App(Box::new(body), Box::new(Term::Var(index)))
+    });
+    let term = (0..30).fold(body, |body, _| Term::Abs(Box::new(body)));
+    let expr = Expr::from(&term);
+
+    let printed = expr.to_string();
+    assert_eq!(printed.matches('λ').count(), 1, "{printed}");
+    assert!(printed.starts_with("λa b c"), "{printed}");
+
+    let options = Options {
+        long_names: true,
+        ..Options::default()
+    };
+    let parsed = lambda_calculus::parse_with(&printed, options).unwrap();
+    assert!(alpha_eq(&parsed, &expr), "{printed}");
+    assert_eq!(Term::from(&parsed), term);
+}
+
+#[test]
+fn alpha_equivalence() {
+    assert!(alpha_eq(&parse("λab.a"), &parse("λxy.x")));
+    assert!(alpha_eq(&parse("λa.λb.a"), &parse("λxy.x")));
+    assert!(!alpha_eq(&parse("λa.b"), &parse("λa.c")));
+    assert!(!alpha_eq(&parse("λab.a"), &parse("λab.b")));
+    // a bound variable is never equivalent to a free one of the same name
+    assert!(!alpha_eq(&parse("λa.a"), &parse("λb.a")));
+}
@@ -0,0 +1,36 @@
+use lambda_calculus::{debruijn::alpha_eq, decode, parser::Expr};
+
+fn parse(input: &str) -> Expr {
+    lambda_calculus::parse(input).unwrap()
+}
+
+#[test]
+fn decodes_pairs() {
+    let (first, second) = decode::church_pair(&parse("λf.f a (λx.x)")).unwrap();
+    assert_eq!(first, parse("a"));
+    assert!(alpha_eq(&second, &parse("λx.x")));
+    assert_eq!(decode::church_pair(&parse("λf.f a")), None);
+    assert_eq!(decode::church_pair(&parse("λf.g a b")), None);
+}
+
+#[test]
+fn decodes_scott_lists() {
+    let list = decode::scott_list(&parse("λnc.c a (λnc.c b (λnc.n))")).unwrap();
+    assert_eq!(list, [parse("a"), parse("b")]);
+    assert_eq!(decode::scott_list(&parse("λnc.n")), Some(Vec::new()));
+    assert_eq!(decode::scott_list(&parse("λnc.c a n")), None);
+}
+
+#[test]
+fn describes_values() {
+    let describe = |input| decode::describe(&parse(input));
+    assert_eq!(describe("λfx.f (f (f x))").as_deref(), Some("3"));
+    assert_eq!(describe("λab.a").as_deref(), Some("TRUE"));
+    // several readings are all listed
+    assert_eq!(describe("λab.b").as_deref(), Some("0 = FALSE"));
+    assert_eq!(
+        describe("λf.f (λfx.f x) (λcn.c (λab.b) n)").as_deref(),
+        Some("(1, [0])")
+    );
+    assert_eq!(describe("λx.x"), None);
+}
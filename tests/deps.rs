@@ -0,0 +1,124 @@
+use lambda_calculus::{
+    decode,
+    deps::{self, Combinator, Graph},
+    diagnostic::{DiagnosticKind, Severity},
+    eval::{self, Limits, Strategy},
+    parser::{Options, Statement},
+    prelude,
+};
+
+const PROGRAM: &str = "\
+FACT := λn.ISZERO n 1 (MUL n (FACT (PRED n)))
+EVEN := λn.ISZERO n TRUE (ODD (PRED n))
+ODD := λn.ISZERO n FALSE (EVEN (PRED n))
+TWO := SUCC 1
+THREE := SUCC TWO";
+
+fn parse(input: &str) -> Vec<Statement> {
+    lambda_calculus::parse_program(input, Options::default()).unwrap()
+}
+
+#[test]
+fn dependencies_and_cycles() {
+    let graph = Graph::new(&parse(PROGRAM));
+    assert_eq!(graph.dependencies("THREE"), ["TWO"]);
+    assert_eq!(graph.dependencies("EVEN"), ["ODD"]);
+    assert_eq!(graph.cycles(), [vec!["FACT"], vec!["EVEN", "ODD"]]);
+}
+
+#[test]
+fn cycles_are_reported_with_every_definition_labelled() {
+    let diagnostics = deps::check(&parse(PROGRAM));
+    assert_eq!(diagnostics.len(), 2);
+    assert_eq!(diagnostics[0].message, "Recursive definition `FACT`");
+    assert_eq!(
+        diagnostics[1].message,
+        "Mutually recursive definitions `EVEN` and `ODD`"
+    );
+    assert_eq!(
+        diagnostics[1].kind,
+        DiagnosticKind::RecursiveDefinition {
+            labels: vec![
+                (47..51, "`EVEN` refers to `ODD`".to_string()),
+                (88..91, "`ODD` refers to `EVEN`".to_string()),
+            ]
+        }
+    );
+}
+
+#[test]
+fn rewritten_definitions_are_not_recursive() {
+    let mut statements = parse(PROGRAM);
+    deps::rewrite(&mut statements, Combinator::Y);
+    assert!(Graph::new(&statements).cycles().is_empty());
+
+    let mut env = prelude::env();
+    for statement in statements {
+        if let Statement::Definition { name, expr, .. } = statement {
+            env.define(name, expr);
+        }
+    }
+    let eval = |input: &str| eval::reduce(lambda_calculus::parse(input).unwrap(), &env).0;
+    assert_eq!(decode::church_numeral(&eval("FACT THREE")), Some(6));
+    assert_eq!(decode::church_bool(&eval("ODD THREE")), Some(true));
+    assert_eq!(decode::church_bool(&eval("EVEN THREE")), Some(false));
+}
+
+#[test]
+fn cycles_without_abstractions_are_not_rewritten() {
+    let mut statements = parse("F := F\nG := λx.G x");
+    deps::rewrite(&mut statements, Combinator::Y);
+    assert_eq!(Graph::new(&statements).cycles(), [vec!["F"]]);
+}
+
+#[test]
+fn strict_strategies_use_the_z_combinator() {
+    assert_eq!(
+        Combinator::for_strategy(Strategy::Normal),
+        Some(Combinator::Y)
+    );
+    assert_eq!(
+        Combinator::for_strategy(Strategy::CallByValue),
+        Some(Combinator::Z)
+    );
+    // applicative order reduces under λ, so it unfolds any combinator forever
+    assert_eq!(Combinator::for_strategy(Strategy::Applicative), None);
+}
+
+#[test]
+fn z_rewritten_definitions_evaluate_under_call_by_value() {
+    let mut statements = parse("F := λn.ISZERO n (λd.a) (λd.F (PRED n)) I");
+    deps::rewrite(&mut statements, Combinator::Z);
+
+    let mut env = prelude::env();
+    for statement in statements {
+        if let Statement::Definition { name, expr, .. } = statement {
+            env.define(name, expr);
+        }
+    }
+    let limits = Limits {
+        fuel: Some(10_000),
+        ..Limits::default()
+    };
+    let expr = lambda_calculus::parse("F 2").unwrap();
+    let (normal, _) =
+        eval::reduce_with(expr, &env, Strategy::CallByValue, limits, |_, _| {}).unwrap();
+    assert_eq!(normal, lambda_calculus::parse("a").unwrap());
+}
+
+#[test]
+fn cycles_without_abstractions_are_errors() {
+    let diagnostics = deps::check(&parse("F := F\nA := B\nB := A\nG := λx.G x"));
+    let severities = diagnostics
+        .iter()
+        .map(|diagnostic| diagnostic.severity)
+        .collect::<Vec<_>>();
+    assert_eq!(
+        severities,
+        [Severity::Error, Severity::Error, Severity::Warning]
+    );
+    assert_eq!(
+        diagnostics[1].help.as_deref(),
+        Some("they only expand to each other, so evaluating them never ends under any strategy")
+    );
+}
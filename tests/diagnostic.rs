@@ -0,0 +1,78 @@
+use lambda_calculus::diagnostic::{self, DiagnosticKind};
+
+#[test]
+fn unexpected_token() {
+    let diagnostics = lambda_calculus::parse("λx.x )").unwrap_err();
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].kind, DiagnosticKind::UnexpectedToken);
+    // spans are byte offsets, `λ` takes two bytes
+    assert_eq!(diagnostics[0].span, 6..7);
+    assert_eq!(diagnostics[0].found.as_deref(), Some(")"));
+    assert!(diagnostics[0].expected.contains(&None));
+}
+
+#[test]
+fn unexpected_end_of_input() {
+    let diagnostics = lambda_calculus::parse("λx.").unwrap_err();
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].span, 4..5);
+    assert_eq!(diagnostics[0].found, None);
+    assert!(diagnostics[0].expected.contains(&Some("(".to_string())));
+    assert_eq!(
+        diagnostics[0].message,
+        "Unexpected end of input, expected (, name, number, λ"
+    );
+}
+
+#[test]
+fn unclosed_delimiter() {
+    let diagnostics = lambda_calculus::parse("f (a").unwrap_err();
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].span, 4..5);
+    assert_eq!(
+        diagnostics[0].kind,
+        DiagnosticKind::UnclosedDelimiter {
+            delimiter: "(".to_string(),
+            span: 2..3
+        }
+    );
+}
+
+#[test]
+fn missing_dot() {
+    let diagnostics = lambda_calculus::parse("λx (x)").unwrap_err();
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].kind, DiagnosticKind::Custom);
+    assert_eq!(diagnostics[0].span, 2..3);
+    assert_eq!(diagnostics[0].message, "Expected `.` after the parameters");
+}
+
+#[test]
+fn renders_reports() {
+    let input = "λx.x )";
+    let diagnostics = lambda_calculus::parse(input).unwrap_err();
+    let mut out = Vec::new();
+    diagnostic::write(&diagnostics, "test", input, &mut out).unwrap();
+    let out = String::from_utf8(out).unwrap();
+    assert!(out.contains(&diagnostics[0].message), "{out}");
+    // ariadne counts chars, so the `)` is in column 6
+    assert!(out.contains("test:1:6"), "{out}");
+}
+
+#[test]
+fn renders_json() {
+    let diagnostics = lambda_calculus::parse("λx.x )").unwrap_err();
+    let json = diagnostic::to_json(&diagnostics, "test");
+    assert!(
+        json.starts_with(
+            r#"[{"source":"test","span":{"start":6,"end":7},"kind":{"type":"unexpected_token"},"#
+        ),
+        "{json}"
+    );
+    assert!(
+        json.ends_with(
+            r#""found":")","message":"Unexpected token in input, expected end of input, (, name, number, λ","help":null,"fix":[]}]"#
+        ),
+        "{json}"
+    );
+}
@@ -0,0 +1,183 @@
+use lambda_calculus::debruijn::alpha_eq;
+use lambda_calculus::eval::{self, Env, GaveUp, Limit, Limits, Strategy};
+use lambda_calculus::parser::Expr;
+use std::time::Duration;
+
+const OMEGA: &str = "(λx.x x) (λx.x x)";
+
+fn parse(input: &str) -> Expr {
+    lambda_calculus::parse(input).unwrap()
+}
+
+fn env(definitions: &[(&str, &str)]) -> Env {
+    let mut env = Env::new();
+    for (name, expr) in definitions {
+        env.define(name.to_string(), parse(expr));
+    }
+    env
+}
+
+fn give_up(input: &str, env: &Env, strategy: Strategy, limits: Limits) -> GaveUp {
+    match eval::reduce_with(parse(input), env, strategy, limits, |_, _| {}) {
+        Ok((normal, _)) => panic!("{input} reduced to {normal}"),
+        Err(gave_up) => gave_up,
+    }
+}
+
+const STRATEGIES: [Strategy; 5] = [
+    Strategy::Normal,
+    Strategy::Applicative,
+    Strategy::CallByName,
+    Strategy::CallByValue,
+    Strategy::CallByNeed,
+];
+
+#[test]
+fn gives_up_when_out_of_fuel() {
+    let limits = Limits {
+        fuel: Some(10),
+        ..Limits::default()
+    };
+    for strategy in STRATEGIES {
+        let gave_up = give_up(OMEGA, &Env::new(), strategy, limits);
+        assert_eq!(gave_up.limit, Limit::Fuel(10), "{strategy:?}");
+        assert_eq!(gave_up.steps, 10, "{strategy:?}");
+    }
+}
+
+#[test]
+fn gives_up_after_the_timeout() {
+    let limits = Limits {
+        timeout: Some(Duration::ZERO),
+        ..Limits::default()
+    };
+    for strategy in STRATEGIES {
+        let gave_up = give_up(OMEGA, &Env::new(), strategy, limits);
+        assert_eq!(
+            gave_up.limit,
+            Limit::Timeout(Duration::ZERO),
+            "{strategy:?}"
+        );
+    }
+}
+
+#[test]
+fn gives_up_when_the_term_grows_too_large() {
+    let limits = Limits {
+        max_size: Some(100),
+        ..Limits::default()
+    };
+    let growing = "(λx.x x x) (λx.x x x)";
+    for strategy in STRATEGIES {
+        let gave_up = give_up(growing, &Env::new(), strategy, limits);
+        assert_eq!(gave_up.limit, Limit::Size(100), "{strategy:?}");
+    }
+}
+
+#[test]
+fn size_limit_applies_to_terms_without_redexes() {
+    let limits = Limits {
+        max_size: Some(100),
+        ..Limits::default()
+    };
+    for strategy in STRATEGIES {
+        let gave_up = give_up("1000", &Env::new(), strategy, limits);
+        assert_eq!(gave_up.limit, Limit::Size(100), "{strategy:?}");
+        assert_eq!(gave_up.steps, 0, "{strategy:?}");
+    }
+}
+
+#[test]
+fn expanding_globals_counts_toward_the_fuel() {
+    let limits = Limits {
+        fuel: Some(100),
+        ..Limits::default()
+    };
+    let looping = env(&[("F", "F")]);
+    let mutual = env(&[("A", "B"), ("B", "A")]);
+    for strategy in STRATEGIES {
+        let gave_up = give_up("F x", &looping, strategy, limits);
+        assert_eq!(gave_up.limit, Limit::Fuel(100), "{strategy:?}");
+        let gave_up = give_up("A", &mutual, strategy, limits);
+        assert_eq!(gave_up.limit, Limit::Fuel(100), "{strategy:?}");
+    }
+}
+
+#[test]
+fn expanding_globals_without_fuel_stops_at_the_timeout() {
+    let limits = Limits {
+        timeout: Some(Duration::from_millis(50)),
+        ..Limits::default()
+    };
+    let mutual = env(&[("A", "B"), ("B", "A")]);
+    for strategy in STRATEGIES {
+        let gave_up = give_up("A", &mutual, strategy, limits);
+        assert!(matches!(gave_up.limit, Limit::Timeout(_)), "{strategy:?}");
+    }
+}
+
+#[test]
+fn lazy_strategies_skip_unused_diverging_arguments() {
+    let limits = Limits {
+        fuel: Some(1000),
+        ..Limits::default()
+    };
+    let input = format!("(λx.y) ({OMEGA})");
+    for strategy in [Strategy::Normal, Strategy::CallByName, Strategy::CallByNeed] {
+        let reduced = eval::reduce_with(parse(&input), &Env::new(), strategy, limits, |_, _| {});
+        let (normal, steps) = reduced.unwrap_or_else(|gave_up| panic!("{strategy:?} {gave_up}"));
+        assert_eq!(normal, Expr::name("y"), "{strategy:?}");
+        assert_eq!(steps, 1, "{strategy:?}");
+    }
+    for strategy in [Strategy::Applicative, Strategy::CallByValue] {
+        let gave_up = give_up(&input, &Env::new(), strategy, limits);
+        assert_eq!(gave_up.limit, Limit::Fuel(1000), "{strategy:?}");
+    }
+}
+
+#[test]
+fn call_by_need_shares_arguments() {
+    // the argument reduces to `λz.z` in two steps, which call-by-name repeats for every use
+    let input = "(λx.x x x) ((λy.y) ((λy.y) (λz.z)))";
+    let steps = |strategy| {
+        let reduced = eval::reduce_with(
+            parse(input),
+            &Env::new(),
+            strategy,
+            Limits::default(),
+            |_, _| {},
+        );
+        let (normal, steps) = reduced.unwrap();
+        assert!(
+            alpha_eq(&normal, &parse("λz.z")),
+            "{strategy:?} reduced to {normal}"
+        );
+        steps
+    };
+    let (by_name, by_need) = (steps(Strategy::CallByName), steps(Strategy::CallByNeed));
+    assert!(
+        by_need < by_name,
+        "call-by-need took {by_need} steps, call-by-name {by_name}"
+    );
+}
+
+#[test]
+fn substitution_avoids_capture() {
+    let (normal, _) = eval::reduce(parse("(λab.a b) b"), &Env::new());
+    assert!(alpha_eq(&normal, &parse("λc.b c")), "reduced to {normal}");
+    assert!(!alpha_eq(&normal, &parse("λb.b b")), "reduced to {normal}");
+}
+
+#[test]
+fn report_gave_up_writes_to_the_given_output() {
+    let limits = Limits {
+        fuel: Some(3),
+        ..Limits::default()
+    };
+    let gave_up = give_up(OMEGA, &Env::new(), Strategy::Normal, limits);
+    let mut out = Vec::new();
+    lambda_calculus::report_gave_up(&gave_up, "raise it", Default::default(), &mut out).unwrap();
+    let out = String::from_utf8(out).unwrap();
+    assert!(out.contains("Evaluation gave up after 3 steps"), "{out}");
+    assert!(out.contains("raise it"), "{out}");
+}
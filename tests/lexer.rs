@@ -0,0 +1,88 @@
+use lambda_calculus::{
+    diagnostic::DiagnosticKind,
+    lexer::Token,
+    parser::{Numerals, Options},
+};
+
+#[test]
+fn tokens_carry_byte_spans() {
+    assert_eq!(
+        lambda_calculus::tokenize("I := λx.x\n  12"),
+        vec![
+            (Token::Ident("I"), 0..1),
+            (Token::Binding, 2..4),
+            (Token::Lambda, 5..7),
+            (Token::Ident("x"), 7..8),
+            (Token::Dot, 8..9),
+            (Token::Ident("x"), 9..10),
+            (Token::Number(12), 13..15),
+        ]
+    );
+}
+
+#[test]
+fn invalid_characters_are_reported_with_suggestions() {
+    let diagnostics = lambda_calculus::parse("λx.x - x §§").unwrap_err();
+    assert_eq!(diagnostics.len(), 2);
+    assert_eq!(diagnostics[0].kind, DiagnosticKind::InvalidCharacter);
+    assert_eq!(diagnostics[0].span, 6..7);
+    assert_eq!(diagnostics[0].found.as_deref(), Some("-"));
+    assert_eq!(diagnostics[0].help.as_deref(), Some("did you mean `->`?"));
+    // consecutive invalid characters are reported together
+    assert_eq!(diagnostics[1].span, 10..14);
+    assert_eq!(diagnostics[1].message, "Invalid characters `§§`");
+}
+
+#[test]
+fn names_mixing_cases_or_digits_are_reported() {
+    let diagnostics = lambda_calculus::parse("Foo x1 ab").unwrap_err();
+    assert_eq!(diagnostics.len(), 2);
+    assert_eq!(diagnostics[0].kind, DiagnosticKind::InvalidName);
+    assert_eq!(diagnostics[0].found.as_deref(), Some("Foo"));
+    assert_eq!(diagnostics[0].help.as_deref(), Some("did you mean `FOO`?"));
+    assert_eq!(diagnostics[1].span, 4..6);
+}
+
+#[test]
+fn comments_are_skipped() {
+    let input = "-- identity\nI := λx.x -- trailing\n\n  -- indented\n{- block {- nested -} -}\nK := {- inline -} λab.a\nK I";
+    let statements = lambda_calculus::parse_program(input, Options::default()).unwrap();
+    assert_eq!(statements.len(), 3);
+    assert_eq!(
+        lambda_calculus::parse_program("I := λx.x\n-- between\nI", Options::default()),
+        lambda_calculus::parse_program("I := λx.x\nI", Options::default())
+    );
+}
+
+#[test]
+fn unterminated_block_comments_point_at_the_opening() {
+    let diagnostics = lambda_calculus::parse("x {- a {- b -} c").unwrap_err();
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].kind, DiagnosticKind::UnterminatedComment);
+    assert_eq!(diagnostics[0].span, 2..4);
+}
+
+#[test]
+fn numbers_too_large_for_their_numerals_are_reported() {
+    let diagnostics = lambda_calculus::parse("ISZERO 3000000").unwrap_err();
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].kind, DiagnosticKind::NumberTooLarge);
+    assert_eq!(diagnostics[0].span, 7..14);
+    assert_eq!(
+        diagnostics[0].help.as_deref(),
+        Some("Church numerals can be at most 10000")
+    );
+
+    let binary = Options {
+        numerals: Numerals::Binary,
+        ..Options::default()
+    };
+    assert!(lambda_calculus::parse_with("3000000", binary).is_ok());
+    let diagnostics = lambda_calculus::parse_with("(99999999999999999999999)", binary).unwrap_err();
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].kind, DiagnosticKind::NumberTooLarge);
+    assert_eq!(
+        diagnostics[0].message,
+        "Number `99999999999999999999999` is too large"
+    );
+}
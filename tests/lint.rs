@@ -0,0 +1,89 @@
+use lambda_calculus::{
+    diagnostic::{self, Diagnostic, DiagnosticKind},
+    lint::{self, Lint, Lints},
+    parser::Options,
+};
+
+fn lint(input: &str) -> Vec<Diagnostic> {
+    let statements = lambda_calculus::parse_program(input, Options::default()).unwrap();
+    lint::check(input, &statements, &Lints::default())
+}
+
+fn lints(diagnostics: &[Diagnostic]) -> Vec<Lint> {
+    diagnostics
+        .iter()
+        .map(|diagnostic| match diagnostic.kind {
+            DiagnosticKind::Lint { lint, .. } => lint,
+            _ => panic!("not a lint: {diagnostic:?}"),
+        })
+        .collect()
+}
+
+fn fixed(input: &str) -> String {
+    diagnostic::apply_fixes(input, &lint(input))
+}
+
+#[test]
+fn parameters() {
+    let diagnostics = lint("λab.a\nλaa.a\nλa.λa.a");
+    assert_eq!(
+        lints(&diagnostics),
+        [
+            Lint::UnusedParameter,
+            Lint::DuplicateParameter,
+            Lint::UnusedParameter,
+            Lint::Shadowing,
+        ]
+    );
+    // the second `a` is the duplicate, pointing back at the first
+    assert_eq!(diagnostics[1].span, 10..11);
+    assert_eq!(
+        diagnostics[1].kind,
+        DiagnosticKind::Lint {
+            lint: Lint::DuplicateParameter,
+            related: Some(9..10)
+        }
+    );
+}
+
+#[test]
+fn numerals_dont_count_as_unused_parameters() {
+    assert_eq!(lint("λf.f 0"), []);
+}
+
+#[test]
+fn shadowed_parameters_are_renamed() {
+    assert_eq!(fixed("λab.a (λa.a b) b"), "λab.a (λc.c b) b");
+}
+
+#[test]
+fn unused_definitions_are_removed() {
+    assert_eq!(
+        fixed("I := (λx.x) -- identity\nK := λab.a\nK"),
+        "K := λab.a\nK"
+    );
+    // without expressions, the definitions are used by whoever loads them
+    assert_eq!(lint("I := λx.x"), []);
+}
+
+#[test]
+fn redundant_parentheses_are_removed() {
+    assert_eq!(fixed("(f a) ((2)) (λx.x) (g)"), "f a 2 (λx.x) g");
+    assert_eq!(
+        fixed("I := (λx.x)\nI (λx.x)\nf(x)"),
+        "I := λx.x\nI λx.x\nf x"
+    );
+    assert_eq!(lint("f (g a) (λx.x) b\n(λx.x) a"), []);
+}
+
+#[test]
+fn lints_can_be_disabled() {
+    let input = "λab.(a)";
+    let statements = lambda_calculus::parse_program(input, Options::default()).unwrap();
+    let mut lints = Lints::default();
+    lints.disable(Lint::UnusedParameter);
+    let diagnostics = lint::check(input, &statements, &lints);
+    assert_eq!(self::lints(&diagnostics), [Lint::RedundantParentheses]);
+    assert!(lint::check(input, &statements, &Lints::none()).is_empty());
+    assert_eq!("shadowing".parse(), Ok(Lint::Shadowing));
+}
@@ -0,0 +1,335 @@
+use lambda_calculus::parser::{
+    Direction, Expr, Lambda, Numerals, Options, Param, Statement, Style,
+};
+
+fn parse(input: &str) -> Expr {
+    lambda_calculus::parse(input).unwrap()
+}
+
+fn name(name: &str) -> Expr {
+    Expr::name(name)
+}
+
+fn app(callee: Expr, argument: Expr) -> Expr {
+    Expr::application(callee, argument)
+}
+
+fn abs(params: &str, body: Expr) -> Expr {
+    Expr::abstraction(params.chars().map(Param::new).collect(), body)
+}
+
+#[test]
+fn application_is_left_associative() {
+    assert_eq!(parse("f a b"), app(app(name("f"), name("a")), name("b")));
+}
+
+#[test]
+fn parentheses_group_arguments() {
+    assert_eq!(parse("f (a b)"), app(name("f"), app(name("a"), name("b"))));
+    assert_eq!(parse("((f))"), name("f"));
+}
+
+#[test]
+fn parenthesized_abstraction_as_callee() {
+    assert_eq!(parse("(λx.x) y"), app(abs("x", name("x")), name("y")));
+}
+
+#[test]
+fn abstraction_body_extends_right() {
+    assert_eq!(parse("λx.x x"), abs("x", app(name("x"), name("x"))));
+    assert_eq!(
+        parse("λx.λy.x y"),
+        abs("x", abs("y", app(name("x"), name("y"))))
+    );
+}
+
+#[test]
+fn abstraction_as_last_argument() {
+    assert_eq!(
+        parse("f λx.x y"),
+        app(name("f"), abs("x", app(name("x"), name("y"))))
+    );
+}
+
+#[test]
+fn multiple_parameters() {
+    assert_eq!(parse("λab.a"), abs("ab", name("a")));
+    assert_eq!(parse("λab.PAIR a"), abs("ab", app(name("PAIR"), name("a"))));
+}
+
+#[test]
+fn rejects_malformed_expressions() {
+    assert!(lambda_calculus::parse("f (a").is_err());
+    assert!(lambda_calculus::parse("λ.x").is_err());
+    assert!(lambda_calculus::parse("λx x").is_err());
+    assert!(lambda_calculus::parse("").is_err());
+}
+
+#[test]
+fn expressions_may_span_lines_and_end_in_a_newline() {
+    assert_eq!(parse("x\n"), name("x"));
+    assert_eq!(parse("λx.\nx\r\n"), abs("x", name("x")));
+}
+
+#[test]
+fn definitions_end_before_next_binding() {
+    let statements =
+        lambda_calculus::parse_program("I := λx.x a\nK := λab.a\nK I", Options::default()).unwrap();
+    assert_eq!(
+        statements,
+        vec![
+            Statement::Definition {
+                name: "I".to_string(),
+                span: None,
+                expr: abs("x", app(name("x"), name("a"))),
+            },
+            Statement::Definition {
+                name: "K".to_string(),
+                span: None,
+                expr: abs("ab", name("a")),
+            },
+            Statement::Expr(app(name("K"), name("I"))),
+        ]
+    );
+}
+
+#[test]
+fn indented_lines_continue_a_statement() {
+    let statements =
+        lambda_calculus::parse_program("\nK := λab.\n  a\n\n\nK\n", Options::default()).unwrap();
+    assert_eq!(
+        statements,
+        vec![
+            Statement::Definition {
+                name: "K".to_string(),
+                span: None,
+                expr: abs("ab", name("a")),
+            },
+            Statement::Expr(name("K")),
+        ]
+    );
+}
+
+#[test]
+fn applications_are_single_statements() {
+    let statements =
+        lambda_calculus::parse_program("(λx.x) y\nPAIR a b", Options::default()).unwrap();
+    assert_eq!(
+        statements,
+        vec![
+            Statement::Expr(app(abs("x", name("x")), name("y"))),
+            Statement::Expr(app(app(name("PAIR"), name("a")), name("b"))),
+        ]
+    );
+}
+
+#[test]
+fn statements_need_line_breaks() {
+    assert!(lambda_calculus::parse_program("I := λx.x K := λab.a", Options::default()).is_err());
+}
+
+#[test]
+fn numbers_desugar_to_numerals() {
+    assert_eq!(
+        parse("3"),
+        abs(
+            "fx",
+            app(name("f"), app(name("f"), app(name("f"), name("x"))))
+        )
+    );
+    assert_eq!(
+        parse("ADD 0 1"),
+        app(app(name("ADD"), parse("λfx.x")), parse("λfx.f x"))
+    );
+    assert_eq!(Numerals::Scott.encode(2), parse("λzs.s (λzs.s (λzs.z))"));
+    assert_eq!(
+        Numerals::Binary.encode(6),
+        parse("λzot.o (λzot.t (λzot.t (λzot.z)))")
+    );
+    assert_eq!(Numerals::Binary.encode(0), parse("λzot.z"));
+}
+
+#[test]
+fn printed_expressions_parse_back() {
+    for input in [
+        "f a b",
+        "f (a b)",
+        "(λx.x) y",
+        "λab.a (f λx.x) c",
+        "f λx.x y",
+    ] {
+        let expr = parse(input);
+        assert_eq!(parse(&expr.to_string()), expr, "{input}");
+    }
+}
+
+#[test]
+fn ascii_lambda_syntax() {
+    let expected = abs("ab", app(name("a"), name("b")));
+    assert_eq!(parse(r"\ab.a b"), expected);
+    assert_eq!(parse("lambda ab. a b"), expected);
+    assert_eq!(parse(r"\a b -> a b"), expected);
+}
+
+#[test]
+fn printed_ascii_expressions_parse_back() {
+    let expr = parse("λab.a (f λx.x) c");
+    let style = Style {
+        lambda: Lambda::Ascii,
+        ..Style::default()
+    };
+    let ascii = expr.display(style).to_string();
+    assert_eq!(ascii, r"\ab.a (f \x.x) c");
+    assert_eq!(parse(&ascii), expr);
+}
+
+#[test]
+fn long_names() {
+    let long_names = Options {
+        long_names: true,
+        ..Options::default()
+    };
+    let parse_long = |input| lambda_calculus::parse_with(input, long_names).unwrap();
+    let expected = Expr::abstraction(
+        vec![Param::new("f"), Param::new("acc"), Param::new("x1")],
+        app(app(name("f"), name("acc")), name("x1")),
+    );
+
+    assert_eq!(parse_long("λf acc x1.f acc x1"), expected);
+    assert_eq!(parse_long("λ(f acc x1).f acc x1"), expected);
+    assert_eq!(parse_long("λab.ab"), abs_long("ab", name("ab")));
+    assert_eq!(parse_long(&expected.to_string()), expected);
+    // without long names, a word is a sequence of single letter variables
+    assert_eq!(parse("λab.ab"), abs("ab", app(name("a"), name("b"))));
+    assert!(lambda_calculus::parse("λx1.x1").is_err());
+}
+
+#[test]
+fn single_letter_parameters_are_separated_with_long_names() {
+    let long_names = Options {
+        long_names: true,
+        ..Options::default()
+    };
+    let style = Style {
+        long_names: true,
+        ..Style::default()
+    };
+    let expr = lambda_calculus::parse_with("λa b.a", long_names).unwrap();
+    let printed = expr.display(style).to_string();
+    assert_eq!(printed, "λa b.a");
+    assert_eq!(
+        lambda_calculus::parse_with(&printed, long_names).unwrap(),
+        expr
+    );
+}
+
+#[test]
+fn lambda_is_a_keyword_only_as_a_whole_word_with_long_names() {
+    let long_names = Options {
+        long_names: true,
+        ..Options::default()
+    };
+    let parse_long = |input| lambda_calculus::parse_with(input, long_names).unwrap();
+    assert_eq!(parse_long("lambdas"), name("lambdas"));
+    assert_eq!(parse_long("xlambda"), name("xlambda"));
+    assert_eq!(
+        parse_long("lambda lambdas.lambdas"),
+        abs_long("lambdas", name("lambdas"))
+    );
+}
+
+fn abs_long(param: &str, body: Expr) -> Expr {
+    Expr::abstraction(vec![Param::new(param)], body)
+}
+
+#[test]
+fn nodes_carry_their_spans() {
+    // `λ` takes two bytes
+    let expr = parse("λx y.(f x) 12");
+    assert_eq!(expr.span(), Some(0..14));
+    let Expr::Abstraction { params, body, .. } = expr else {
+        panic!("not an abstraction");
+    };
+    assert_eq!(params[0].span, Some(2..3));
+    assert_eq!(params[1].span, Some(4..5));
+    assert_eq!(body.span(), Some(6..14));
+    let Expr::Application {
+        callee, argument, ..
+    } = *body
+    else {
+        panic!("not an application");
+    };
+    assert_eq!(callee.span(), Some(7..10));
+    assert_eq!(argument.span(), Some(12..14));
+    let statements = lambda_calculus::parse_program("\nTWO := 2", Options::default()).unwrap();
+    let Statement::Definition { span, .. } = &statements[0] else {
+        panic!("not a definition");
+    };
+    assert_eq!(*span, Some(1..4));
+}
+
+#[test]
+fn spans_are_ignored_by_equality() {
+    assert_eq!(parse("f  x"), parse("f x"));
+}
+
+#[test]
+fn recovers_from_errors_in_every_statement() {
+    let input = "I := λx x\nK := λxy.x\nf (a b\nK ) I\nλx (x)";
+    let (statements, diagnostics) =
+        lambda_calculus::parse_program_partial(input, Options::default());
+
+    let spans = diagnostics
+        .iter()
+        .map(|diagnostic| diagnostic.span.clone())
+        .collect::<Vec<_>>();
+    assert_eq!(spans, [10..11, 29..30, 32..33, 38..39]);
+
+    let error = |start, end| Expr::Error {
+        span: Some(start..end),
+    };
+    assert_eq!(
+        statements,
+        [
+            Statement::Definition {
+                name: "I".to_string(),
+                span: None,
+                expr: error(5, 10)
+            },
+            Statement::Definition {
+                name: "K".to_string(),
+                span: None,
+                expr: abs("xy", name("x"))
+            },
+            Statement::Expr(error(23, 29)),
+            Statement::Expr(error(30, 35)),
+            Statement::Expr(abs("x", name("x"))),
+        ]
+    );
+}
+
+#[test]
+fn render_at_highlights_the_subexpression() {
+    let expr = parse("(λx.x) a b");
+    let render = |path: &[Direction]| expr.render_at(path, Style::default());
+
+    assert_eq!(render(&[]), ("(λx.x) a b".to_string(), Some(0..10)));
+    assert_eq!(render(&[Direction::Callee]).1, Some(0..8));
+    // the parentheses around the abstraction aren't part of it, and ranges count characters
+    assert_eq!(
+        render(&[Direction::Callee, Direction::Callee]).1,
+        Some(1..5)
+    );
+    assert_eq!(render(&[Direction::Argument]).1, Some(9..10));
+    assert_eq!(render(&[Direction::Body]).1, None);
+}
+
+#[test]
+fn report_step_writes_to_the_given_output() {
+    let expr = parse("(λx.x) a");
+    let mut out = Vec::new();
+    lambda_calculus::report_step(1, &expr, &[], Style::default(), &mut out).unwrap();
+    let out = String::from_utf8(out).unwrap();
+    assert!(out.contains("Step 1"), "{out}");
+    assert!(out.contains("Contracting this redex"), "{out}");
+}
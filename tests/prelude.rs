@@ -0,0 +1,112 @@
+use lambda_calculus::{debruijn::alpha_eq, decode, eval, parser::Expr, prelude};
+
+fn eval(input: &str) -> Expr {
+    let expr = lambda_calculus::parse(input).unwrap();
+    eval::reduce(expr, &prelude::env()).0
+}
+
+fn number(input: &str) -> usize {
+    decode::church_numeral(&eval(input)).unwrap_or_else(|| panic!("{input} is not a numeral"))
+}
+
+fn boolean(input: &str) -> bool {
+    decode::church_bool(&eval(input)).unwrap_or_else(|| panic!("{input} is not a boolean"))
+}
+
+fn list(input: &str) -> Vec<usize> {
+    decode::church_list(&eval(input))
+        .unwrap_or_else(|| panic!("{input} is not a list"))
+        .iter()
+        .map(|element| decode::church_numeral(element).unwrap())
+        .collect()
+}
+
+fn assert_reduces_to(input: &str, expected: &str) {
+    let expected = lambda_calculus::parse(expected).unwrap();
+    let normal = eval(input);
+    assert!(alpha_eq(&normal, &expected), "{input} reduced to {normal}");
+}
+
+#[test]
+fn combinators() {
+    assert_reduces_to("I a", "a");
+    assert_reduces_to("K a b", "a");
+    assert_reduces_to("S a b c", "a c (b c)");
+    assert_reduces_to("S K K a", "a");
+}
+
+#[test]
+fn booleans() {
+    assert!(boolean("TRUE"));
+    assert!(!boolean("FALSE"));
+    assert!(!boolean("NOT TRUE"));
+    assert!(boolean("NOT FALSE"));
+    for (a, b) in [(false, false), (false, true), (true, false), (true, true)] {
+        let name = |value: bool| if value { "TRUE" } else { "FALSE" };
+        assert_eq!(boolean(&format!("AND {} {}", name(a), name(b))), a && b);
+        assert_eq!(boolean(&format!("OR {} {}", name(a), name(b))), a || b);
+    }
+    assert_reduces_to("IF TRUE a b", "a");
+    assert_reduces_to("IF FALSE a b", "b");
+}
+
+#[test]
+fn pairs() {
+    assert_reduces_to("FIRST (PAIR a b)", "a");
+    assert_reduces_to("SECOND (PAIR a b)", "b");
+}
+
+#[test]
+fn arithmetic() {
+    assert_eq!(number("SUCC 0"), 1);
+    assert_eq!(number("SUCC 4"), 5);
+    assert_eq!(number("ADD 3 4"), 7);
+    assert_eq!(number("MUL 3 4"), 12);
+    assert_eq!(number("MUL 0 4"), 0);
+    assert_eq!(number("POW 2 3"), 8);
+    assert_eq!(number("PRED 0"), 0);
+    assert_eq!(number("PRED 5"), 4);
+    assert_eq!(number("SUB 5 2"), 3);
+    assert_eq!(number("SUB 2 5"), 0);
+}
+
+#[test]
+fn comparisons() {
+    assert!(boolean("ISZERO 0"));
+    assert!(!boolean("ISZERO 2"));
+    assert!(boolean("LEQ 2 3"));
+    assert!(boolean("LEQ 3 3"));
+    assert!(!boolean("LEQ 4 3"));
+    assert!(boolean("EQ 3 3"));
+    assert!(!boolean("EQ 3 2"));
+}
+
+#[test]
+fn fixed_point_combinator() {
+    let factorial = "Y (λfn.IF (ISZERO n) 1 (MUL n (f (PRED n))))";
+    assert_eq!(number(&format!("{factorial} 0")), 1);
+    assert_eq!(number(&format!("{factorial} 3")), 6);
+}
+
+#[test]
+fn lists() {
+    assert_eq!(list("NIL"), []);
+    assert_eq!(list("CONS 1 (CONS 2 NIL)"), [1, 2]);
+    assert!(boolean("ISNIL NIL"));
+    assert!(!boolean("ISNIL (CONS 1 NIL)"));
+    assert_eq!(number("HEAD (CONS 1 (CONS 2 NIL))"), 1);
+    assert_eq!(list("TAIL (CONS 1 (CONS 2 NIL))"), [2]);
+    assert_eq!(list("TAIL NIL"), []);
+    assert_eq!(number("FOLD ADD 0 (CONS 1 (CONS 2 (CONS 3 NIL)))"), 6);
+    assert_eq!(list("MAP SUCC (CONS 1 (CONS 2 NIL))"), [2, 3]);
+    assert_eq!(number("LENGTH (CONS 1 (CONS 1 (CONS 1 NIL)))"), 3);
+    assert_eq!(number("LENGTH NIL"), 0);
+}
+
+#[test]
+fn user_definitions_override_the_prelude() {
+    let mut env = prelude::env();
+    env.define("TRUE".to_string(), Expr::name("yes"));
+    let expr = lambda_calculus::parse("NOT FALSE").unwrap();
+    assert_eq!(eval::reduce(expr, &env).0, Expr::name("yes"));
+}
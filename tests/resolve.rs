@@ -0,0 +1,86 @@
+use lambda_calculus::{
+    diagnostic::{Diagnostic, DiagnosticKind, Severity},
+    eval::Env,
+    parser::{Options, Statement},
+    prelude, resolve,
+};
+
+fn check(input: &str, env: &Env, options: Options) -> Vec<Diagnostic> {
+    let statements = lambda_calculus::parse_program(input, options).unwrap();
+    resolve::check(&statements, env)
+}
+
+#[test]
+fn undefined_globals_are_errors_with_suggestions() {
+    let diagnostics = check("λab.PAIRR a", &prelude::env(), Options::default());
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].severity, Severity::Error);
+    assert_eq!(diagnostics[0].kind, DiagnosticKind::UndefinedName);
+    assert_eq!(diagnostics[0].span, 5..10);
+    assert_eq!(diagnostics[0].help.as_deref(), Some("did you mean `PAIR`?"));
+
+    let diagnostics = check("λab.PAIR a", &Env::new(), Options::default());
+    assert_eq!(diagnostics[0].help, None);
+}
+
+#[test]
+fn free_variables_are_warnings() {
+    let diagnostics = check("λx.x y", &Env::new(), Options::default());
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].severity, Severity::Warning);
+    assert_eq!(diagnostics[0].kind, DiagnosticKind::FreeVariable);
+    assert_eq!(diagnostics[0].found.as_deref(), Some("y"));
+}
+
+#[test]
+fn long_variables_get_suggestions() {
+    let options = Options {
+        long_names: true,
+        ..Options::default()
+    };
+    let diagnostics = check("λacc x.acc x\nλacc.ac\npair 1 2", &prelude::env(), options);
+    let help = diagnostics
+        .iter()
+        .map(|diagnostic| diagnostic.help.as_deref())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        help,
+        [Some("did you mean `acc`?"), Some("did you mean `PAIR`?")]
+    );
+}
+
+#[test]
+fn definitions_can_refer_to_later_ones_but_expressions_cannot() {
+    let diagnostics = check(
+        "E\nE := λn.O n\nO := λn.E n\nE",
+        &Env::new(),
+        Options::default(),
+    );
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].span, 0..1);
+}
+
+#[test]
+fn undefined_globals_in_definitions_are_warnings() {
+    let diagnostics = check("E := λn.O n", &Env::new(), Options::default());
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].severity, Severity::Warning);
+    assert_eq!(diagnostics[0].kind, DiagnosticKind::UndefinedName);
+    assert_eq!(
+        diagnostics[0].help.as_deref(),
+        Some("define it before evaluating anything that uses `E`")
+    );
+
+    // entered one line at a time, like in the REPL
+    let mut env = Env::new();
+    let statements = lambda_calculus::parse_program("E := λn.O n", Options::default()).unwrap();
+    if let Statement::Definition { name, expr, .. } = statements[0].clone() {
+        env.define(name, expr);
+    }
+    assert_eq!(check("O := λn.E n\nE", &env, Options::default()), []);
+}
+
+#[test]
+fn the_prelude_resolves() {
+    assert_eq!(check(prelude::SOURCE, &Env::new(), Options::default()), []);
+}